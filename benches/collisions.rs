@@ -0,0 +1,175 @@
+//! Compares finding collisions by checking every pair against looking them up in a
+//! [SpatialGrid], then times whole steps of a room full of enemies and projectiles.
+//!
+//! usage: `cargo bench --bench collisions`
+
+use std::{collections::HashMap, hint::black_box, time::Instant};
+
+use macroquad::prelude::*;
+use ramble::{
+    enemy::Enemy,
+    items::*,
+    player::*,
+    projectiles::{DamageType, star_explosion},
+    rng::Rng,
+    simulation::*,
+    spatial::SpatialGrid,
+    utils::*,
+    worlds::worlds,
+};
+
+const ENEMIES: usize = 300;
+const PROJECTILES: usize = 1000;
+/// Reach of a projectile with the default radius
+const REACH: f32 = 9.0;
+/// Distance enemies keep from each other
+const SEPARATION: f32 = 7.0;
+const ROUNDS: u32 = 200;
+
+fn random_positions(rng: &mut Rng, count: usize) -> Vec<Vec2> {
+    (0..count)
+        .map(|_| {
+            Vec2::new(
+                rng.gen_range(4.0, SCREEN_WIDTH - 4.0),
+                rng.gen_range(28.0, SCREEN_HEIGHT - 8.0),
+            )
+        })
+        .collect()
+}
+
+/// Runs `f` for every round, and returns the average time a round took in microseconds
+fn time(mut f: impl FnMut() -> usize) -> (f64, usize) {
+    let start = Instant::now();
+    let mut found = 0;
+    for _ in 0..ROUNDS {
+        found = black_box(f());
+    }
+    (start.elapsed().as_secs_f64() * 1e6 / ROUNDS as f64, found)
+}
+
+fn compare(name: &str, brute_force: (f64, usize), grid: (f64, usize)) {
+    assert_eq!(
+        brute_force.1, grid.1,
+        "{name}: the grid found different pairs"
+    );
+    println!(
+        "{name}: every pair {:.1}us, grid {:.1}us ({:.1}x faster, {} pairs)",
+        brute_force.0,
+        grid.0,
+        brute_force.0 / grid.0,
+        grid.1
+    );
+}
+
+/// A room with hundreds of enemies, and a player firing wide fans of razor darts at them that
+/// explode on every hit
+fn crowded_room<'a>(items: &'a [Item], enchantments: &'a [Enchantment]) -> Simulation<'a> {
+    let mut player = Player::new(Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT - 16.0));
+    let stats = player.internal_stats_mut();
+    stats.max_lives = u16::MAX;
+    stats.lives = u16::MAX;
+    stats.merge(&Stats {
+        projectiles: 12,
+        burst: 2,
+        on_hit_effects: HashMap::from([(
+            OnHitTrigger::Hit,
+            vec![(star_explosion(), HashMap::from([(DamageType::Holy, 1.0)]))],
+        )]),
+        ..Default::default()
+    });
+    *player.slot_mut(InventorySlot::Hand).unwrap() =
+        get_item_by_name(items, "razor dart").ok().cloned();
+    *player.slot_mut(InventorySlot::Talisman(0)).unwrap() =
+        get_item_by_name(items, "split shot").ok().cloned();
+    let mut sim = Simulation::new(items, enchantments, player, 0);
+    sim.state = GameState::RoundActive;
+
+    let types = &worlds()[0].light;
+    let mut rng = Rng::new(0);
+    for (index, pos) in random_positions(&mut rng, ENEMIES).into_iter().enumerate() {
+        let mut enemy = Enemy::new(&types[index % types.len()], pos, index, &mut rng);
+        // keep every enemy alive, so the room stays full
+        enemy.health = f32::MAX;
+        sim.enemies.push(enemy);
+    }
+    sim
+}
+
+fn main() {
+    let mut rng = Rng::new(0);
+    let enemies = random_positions(&mut rng, ENEMIES);
+    let projectiles = random_positions(&mut rng, PROJECTILES);
+    let mut grid = SpatialGrid::default();
+    let mut nearby = Vec::new();
+
+    let brute_force = time(|| {
+        let mut hits = 0;
+        for projectile in projectiles.iter() {
+            hits += enemies
+                .iter()
+                .filter(|f| f.distance(*projectile) <= REACH)
+                .count();
+        }
+        hits
+    });
+    let with_grid = time(|| {
+        grid.rebuild(enemies.iter().copied());
+        let mut hits = 0;
+        for projectile in projectiles.iter() {
+            grid.query(*projectile, REACH, &mut nearby);
+            hits += nearby
+                .iter()
+                .filter(|f| enemies[**f].distance(*projectile) <= REACH)
+                .count();
+        }
+        hits
+    });
+    compare("projectile hits", brute_force, with_grid);
+
+    let brute_force = time(|| {
+        let mut pairs = 0;
+        for enemy in enemies.iter() {
+            pairs += enemies
+                .iter()
+                .filter(|f| *f != enemy && f.distance(*enemy) <= SEPARATION)
+                .count();
+        }
+        pairs
+    });
+    let with_grid = time(|| {
+        grid.rebuild(enemies.iter().copied());
+        let mut pairs = 0;
+        for enemy in enemies.iter() {
+            grid.query(*enemy, SEPARATION, &mut nearby);
+            pairs += nearby
+                .iter()
+                .map(|f| enemies[*f])
+                .filter(|f| f != enemy && f.distance(*enemy) <= SEPARATION)
+                .count();
+        }
+        pairs
+    });
+    compare("enemy separation", brute_force, with_grid);
+
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let mut sim = crowded_room(&items, &enchantments);
+    let input = InputFrame {
+        aim: Vec2::new(SCREEN_WIDTH / 2.0, 0.0),
+        attack: true,
+        ..Default::default()
+    };
+    let steps = 600;
+    let mut most_projectiles = 0;
+    let start = Instant::now();
+    for _ in 0..steps {
+        sim.step(&input);
+        most_projectiles = most_projectiles.max(sim.projectiles.len());
+    }
+    let per_step = start.elapsed().as_secs_f64() * 1e3 / steps as f64;
+    println!(
+        "crowded room: {per_step:.3}ms per step with {} enemies and up to {most_projectiles} \
+        projectiles (a frame at 60 fps is 16.7ms)",
+        sim.enemies.len()
+    );
+}
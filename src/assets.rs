@@ -4,10 +4,89 @@ use image::EncodableLayout;
 use macroquad::prelude::*;
 
 use crate::{
-    items::{Enchantment, Item, get_enchantments, get_items},
+    items::{Enchantment, Item, get_enchantments, get_item_by_name, get_items},
+    mods::{self, MODS, Mod},
     utils::*,
 };
 
+/// Directory assets are read from in debug builds
+#[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+pub const ASSETS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets");
+
+/// Contents of a file in `assets/`, given its path in there. Debug builds read it from disk so
+/// it can be hot reloaded, and panic if it can't be read. Release and wasm builds embed it.
+/// `asset!(try path)` returns the error instead of panicking.
+macro_rules! asset {
+    ($path:literal) => {
+        $crate::assets::asset!(try $path).unwrap_or_else(|err| panic!("{err}"))
+    };
+    (try $path:literal) => {{
+        #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+        let bytes = $crate::assets::read_asset($path).map(std::borrow::Cow::<'static, [u8]>::Owned);
+        #[cfg(not(all(debug_assertions, not(target_arch = "wasm32"))))]
+        let bytes = Ok::<_, String>(std::borrow::Cow::<'static, [u8]>::Borrowed(include_bytes!(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/assets/", $path)
+        )));
+        bytes
+    }};
+}
+pub(crate) use asset;
+
+/// Read a file in [ASSETS_DIR], given its path in there
+#[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+pub fn read_asset(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(std::path::Path::new(ASSETS_DIR).join(path))
+        .map_err(|err| format!("failed to read assets/{path}: {err}"))
+}
+
+/// Layer of items.ase the item sprites are on
+const ITEMS_LAYER: Option<u32> = Some(1);
+
+/// Which copy of a spritesheet a sprite is on. Mods have their own copies of the sheets
+/// things they add are drawn from, so their sprites don't collide with the game's.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum SheetId {
+    #[default]
+    BuiltIn,
+    /// Sheet of the mod at this index in [MODS]
+    Mod(usize),
+}
+
+/// A mod's own copies of spritesheets, if it has them
+#[derive(Default)]
+pub struct ModSheets {
+    pub items: Option<Spritesheet>,
+    pub entities: Option<Spritesheet>,
+    pub enchantments: Option<Spritesheet>,
+    pub world: Option<Spritesheet>,
+}
+impl ModSheets {
+    fn load(m: &Mod) -> Self {
+        let sheet = |path: &str| {
+            let bytes = match m.read(path)? {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    mods::report(err);
+                    return None;
+                }
+            };
+            match load_ase_image(&bytes, None) {
+                Ok(image) => Some(Spritesheet::new(image_texture(&image), 16.0)),
+                Err(err) => {
+                    mods::report(format!("{}: {err}", m.path(path)));
+                    None
+                }
+            }
+        };
+        Self {
+            items: sheet("items.ase"),
+            entities: sheet("entities.ase"),
+            enchantments: sheet("enchantments.ase"),
+            world: sheet("world.ase"),
+        }
+    }
+}
+
 pub struct Assets {
     pub all_items: Vec<Item>,
     pub all_enchantments: Vec<Enchantment>,
@@ -20,56 +99,89 @@ pub struct Assets {
     pub curses: Spritesheet,
     pub enchantments: Spritesheet,
     font: Spritesheet,
+    /// Sheets of each mod in [MODS]
+    pub mod_sheets: Vec<ModSheets>,
 }
 impl Default for Assets {
     fn default() -> Self {
         Self {
-            entities: Spritesheet::new(
-                load_ase_texture(include_bytes!("../assets/entities.ase"), None),
-                16.0,
-            ),
-            items: Spritesheet::new(
-                load_ase_texture(include_bytes!("../assets/items.ase"), Some(1)),
-                16.0,
-            ),
+            entities: Spritesheet::new(load_ase_texture(&asset!("entities.ase"), None), 16.0),
+            items: Spritesheet::new(load_ase_texture(&asset!("items.ase"), ITEMS_LAYER), 16.0),
             particles: Spritesheet::new(
-                load_ase_texture(include_bytes!("../assets/particles.ase"), None),
-                16.0,
-            ),
-            ui: Spritesheet::new(
-                load_ase_texture(include_bytes!("../assets/ui.ase"), None),
-                16.0,
-            ),
-            world: Spritesheet::new(
-                load_ase_texture(include_bytes!("../assets/world.ase"), None),
+                load_sheet_texture("particles.ase", &asset!("particles.ase")),
                 16.0,
             ),
-            shop: load_ase_texture(include_bytes!("../assets/shop.ase"), None),
-            curses: Spritesheet::new(
-                load_ase_texture(include_bytes!("../assets/curses.ase"), None),
-                8.0,
-            ),
+            ui: Spritesheet::new(load_sheet_texture("ui.ase", &asset!("ui.ase")), 16.0),
+            world: Spritesheet::new(load_ase_texture(&asset!("world.ase"), None), 16.0),
+            shop: load_sheet_texture("shop.ase", &asset!("shop.ase")),
+            curses: Spritesheet::new(load_sheet_texture("curses.ase", &asset!("curses.ase")), 8.0),
             enchantments: Spritesheet::new(
-                load_ase_texture(include_bytes!("../assets/enchantments.ase"), None),
+                load_ase_texture(&asset!("enchantments.ase"), None),
                 16.0,
             ),
-            font: Spritesheet::new(
-                load_ase_texture(include_bytes!("../assets/font.ase"), None),
-                4.0,
-            ),
+            font: Spritesheet::new(load_sheet_texture("font.ase", &asset!("font.ase")), 4.0),
             all_items: get_items(),
             all_enchantments: get_enchantments(),
+            mod_sheets: MODS.iter().map(ModSheets::load).collect(),
         }
     }
 }
 impl Assets {
-    pub fn get_item_by_name(&self, name: &str) -> &Item {
-        for item in self.all_items.iter() {
-            if item.name == name {
-                return item;
-            }
+    /// Load a texture again from its file in `assets/`, replacing it in place.
+    /// Returns false if the file isn't one of the textures.
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    pub fn reload_texture(&self, path: &str) -> Result<bool, String> {
+        let (texture, layer) = match path {
+            "entities.ase" => (&self.entities.texture, None),
+            "items.ase" => (&self.items.texture, ITEMS_LAYER),
+            "particles.ase" => (&self.particles.texture, None),
+            "ui.ase" => (&self.ui.texture, None),
+            "world.ase" => (&self.world.texture, None),
+            "shop.ase" => (&self.shop, None),
+            "curses.ase" => (&self.curses.texture, None),
+            "enchantments.ase" => (&self.enchantments.texture, None),
+            "font.ase" => (&self.font.texture, None),
+            _ => return Ok(false),
+        };
+        if REPLACEABLE_SHEETS.contains(&path) && mods::override_file(path).is_some() {
+            return Ok(false);
         }
-        panic!()
+        let image = load_ase_image(&read_asset(path)?, layer)?;
+        // resizing replaces the texture's contents even if the size changed
+        unsafe { get_internal_gl() }.quad_context.texture_resize(
+            texture.raw_miniquad_id(),
+            image.width as u32,
+            image.height as u32,
+            Some(&image.bytes),
+        );
+        Ok(true)
+    }
+    pub fn item_sheet(&self, sheet: SheetId) -> &Spritesheet {
+        self.mod_sheet(sheet, |f| &f.items).unwrap_or(&self.items)
+    }
+    pub fn entity_sheet(&self, sheet: SheetId) -> &Spritesheet {
+        self.mod_sheet(sheet, |f| &f.entities)
+            .unwrap_or(&self.entities)
+    }
+    pub fn enchantment_sheet(&self, sheet: SheetId) -> &Spritesheet {
+        self.mod_sheet(sheet, |f| &f.enchantments)
+            .unwrap_or(&self.enchantments)
+    }
+    pub fn world_sheet(&self, sheet: SheetId) -> &Spritesheet {
+        self.mod_sheet(sheet, |f| &f.world).unwrap_or(&self.world)
+    }
+    fn mod_sheet(
+        &self,
+        sheet: SheetId,
+        get: impl Fn(&ModSheets) -> &Option<Spritesheet>,
+    ) -> Option<&Spritesheet> {
+        match sheet {
+            SheetId::BuiltIn => None,
+            SheetId::Mod(index) => get(self.mod_sheets.get(index)?).as_ref(),
+        }
+    }
+    pub fn get_item_by_name(&self, name: &str) -> Result<&Item, String> {
+        get_item_by_name(&self.all_items, name)
     }
     pub fn draw_text(&self, text: &str, mut x: f32, mut y: f32) -> (f32, f32) {
         let original_x = x;
@@ -114,19 +226,50 @@ impl Assets {
     }
 }
 
-fn load_ase_texture(bytes: &[u8], layer: Option<u32>) -> Texture2D {
-    let img = AsepriteFile::read(bytes).unwrap();
+fn load_ase_image(bytes: &[u8], layer: Option<u32>) -> Result<Image, String> {
+    let img = AsepriteFile::read(bytes).map_err(|err| err.to_string())?;
     let img = if let Some(layer) = layer {
+        if layer >= img.num_layers() {
+            return Err(format!("missing layer {layer}"));
+        }
         img.layer(layer).frame(0).image()
     } else {
         img.frame(0).image()
     };
-    let new = Image {
+    Ok(Image {
         width: img.width() as u16,
         height: img.height() as u16,
         bytes: img.as_bytes().to_vec(),
-    };
-    let texture = Texture2D::from_image(&new);
+    })
+}
+
+fn load_ase_texture(bytes: &[u8], layer: Option<u32>) -> Texture2D {
+    image_texture(&load_ase_image(bytes, layer).unwrap())
+}
+
+/// Sheets mods can replace, as they don't have sprites mods could add to
+const REPLACEABLE_SHEETS: [&str; 5] = [
+    "particles.ase",
+    "ui.ase",
+    "shop.ase",
+    "curses.ase",
+    "font.ase",
+];
+
+/// Load one of [REPLACEABLE_SHEETS], from the last mod that replaces it if there is one
+fn load_sheet_texture(path: &str, builtin: &[u8]) -> Texture2D {
+    debug_assert!(REPLACEABLE_SHEETS.contains(&path));
+    if let Some((name, bytes)) = mods::override_file(path) {
+        match load_ase_image(&bytes, None) {
+            Ok(image) => return image_texture(&image),
+            Err(err) => mods::report(format!("mods/{name}/{path}: {err}")),
+        }
+    }
+    load_ase_texture(builtin, None)
+}
+
+fn image_texture(image: &Image) -> Texture2D {
+    let texture = Texture2D::from_image(image);
     texture.set_filter(FilterMode::Nearest);
     texture
 }
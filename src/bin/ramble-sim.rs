@@ -0,0 +1,331 @@
+//! Balance simulator. Plays seeded runs with scripted bots for every class, weapon and bot
+//! policy, and reports how the runs went.
+//!
+//! usage: `ramble-sim [--runs <runs per combination>] [--seed <first seed>] [--policy <name>]`
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use enum_iterator::Sequence;
+use macroquad::prelude::*;
+use ramble::{items::*, player::*, projectiles::DamageType, simulation::*, utils::*};
+
+/// Steps after which a run is given up on, in case a bot gets stuck
+const MAX_STEPS: u32 = 60 * 60 * 20;
+
+#[derive(Clone, Copy, PartialEq, Sequence)]
+enum Policy {
+    /// Keep distance from the closest enemy while circling it and attacking
+    KiteAndShoot,
+    /// Run straight at the closest enemy and attack
+    MeleeRush,
+    /// Kite, and roll out of the way of enemy projectiles about to hit
+    DodgeOnProjectile,
+}
+impl Policy {
+    fn name(self) -> &'static str {
+        match self {
+            Policy::KiteAndShoot => "kite-and-shoot",
+            Policy::MeleeRush => "melee-rush",
+            Policy::DodgeOnProjectile => "dodge-on-projectile",
+        }
+    }
+}
+
+/// Returns the input the bot gives for the next step
+fn bot_input(sim: &Simulation, policy: Policy) -> InputFrame {
+    if let GameState::PostRound(_, _) = sim.state {
+        return post_round_input(sim);
+    }
+    let player = sim.player.pos;
+    let Some(target) = sim
+        .enemies
+        .iter()
+        .min_by(|a, b| a.pos.distance(player).total_cmp(&b.pos.distance(player)))
+    else {
+        return InputFrame::default();
+    };
+    let delta = target.pos - player;
+    let mut direction = match policy {
+        Policy::MeleeRush if delta.length() > 12.0 => delta.normalize(),
+        Policy::MeleeRush => Vec2::ZERO,
+        Policy::KiteAndShoot | Policy::DodgeOnProjectile => {
+            let range = weapon_reach(&sim.player).min(72.0) * 0.8;
+            let away = -delta.normalize_or_zero() * (range - delta.length()) / range;
+            // stay away from the walls, so kiting doesn't end in a corner
+            let center = Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0 + 16.0);
+            away + delta.perp().normalize_or_zero() * 0.5 + (center - player) / SCREEN_HEIGHT
+        }
+    };
+    let mut roll = false;
+    if policy == Policy::DodgeOnProjectile
+        && sim.player.roll_counter <= 0.0
+        && let Some(dodge) = dodge_direction(sim)
+    {
+        direction = dodge;
+        roll = true;
+    }
+    InputFrame {
+        move_vector: to_move_vector(direction),
+        aim: target.pos,
+        // let go of charging attacks once they're fully charged
+        attack: sim.player.charge_fraction().is_none_or(|f| f < 1.0),
+        roll,
+        // use the active item whenever it's ready
+        ability: true,
+        interact: false,
+    }
+}
+
+/// Returns how far the player's weapon hits
+fn weapon_reach(player: &Player) -> f32 {
+    let Some(ItemType::Held(weapon)) = player.slot(InventorySlot::Hand).map(|f| &f.ty) else {
+        return 0.0;
+    };
+    let projectile = &weapon.projectile;
+    let mut speed = projectile.speed;
+    let mut reach = 0.0;
+    for _ in 0..projectile.lifetime.min(600) {
+        speed = speed.lerp(0.0, projectile.drag());
+        reach += speed;
+    }
+    reach
+}
+
+/// Returns which way to roll to dodge, if an enemy projectile is about to hit the player
+fn dodge_direction(sim: &Simulation) -> Option<Vec2> {
+    let player = sim.player.pos;
+    sim.projectiles
+        .iter()
+        .filter(|f| !f.player_owned && f.speed > 0.0)
+        .find_map(|projectile| {
+            let offset = projectile.pos - player;
+            let velocity = projectile.direction * projectile.speed;
+            // closest the projectile gets to the player in the next few steps
+            let time = (-offset.dot(velocity) / velocity.length_squared()).clamp(0.0, 10.0);
+            if (offset + velocity * time).length() > projectile.radius + 6.0 {
+                return None;
+            }
+            let side = velocity.perp();
+            Some(if side.dot(offset) > 0.0 { -side } else { side })
+        })
+}
+
+/// Between rooms, buy a shop offer or pick up an item if it can be equipped right away,
+/// otherwise leave through the door. The bot never swaps its weapon.
+fn post_round_input(sim: &Simulation) -> InputFrame {
+    let player = &sim.player;
+    let mut input = InputFrame {
+        aim: player.pos + RIGHT,
+        ..Default::default()
+    };
+    let equippable = |ty: &ItemType| match ty {
+        ItemType::Helmet => player.slot(InventorySlot::Helmet).is_none(),
+        ItemType::Chestplate => player.slot(InventorySlot::Chestplate).is_none(),
+        ItemType::Talisman => player.talismans().iter().any(Option::is_none),
+        ItemType::Active(_) => player.slot(InventorySlot::Active).is_none(),
+        ItemType::Held(_) => false,
+    };
+
+    let target = if let GameState::PostRound(_, Some(offers)) = &sim.state
+        && let Some(index) = offers.iter().position(|f| equippable(&f.ty))
+    {
+        if sim.shop_item_in_range() == Some(index) {
+            input.interact = true;
+            return input;
+        }
+        Vec2::new(SCREEN_WIDTH / 2.0 - 32.0 + 16.0 * index as f32, shop_y(40))
+    } else if let Some((pos, _)) = sim.dropped_items.iter().find(|f| equippable(&f.1.ty)) {
+        if let Some(index) = sim.item_under_player()
+            && equippable(&sim.dropped_items[index].1.ty)
+        {
+            input.interact = true;
+            return input;
+        }
+        *pos
+    } else {
+        // walk up to the wall and along it in to the door
+        let door_x = (TILES_WIDTH / 2) as f32 * 16.0;
+        let dx = door_x - player.pos.x;
+        let x = if dx.abs() < 4.0 { 0.0 } else { dx.signum() };
+        input.move_vector = movement_vector(x < 0.0, x > 0.0, true, false);
+        return input;
+    };
+    input.move_vector = to_move_vector(target - player.pos);
+    input
+}
+
+/// Convert a direction to one of the 8 directions the keyboard can move in
+fn to_move_vector(direction: Vec2) -> Vec2 {
+    let threshold = direction.length() * 0.4;
+    if direction.length() < 0.05 {
+        return Vec2::ZERO;
+    }
+    movement_vector(
+        direction.x < -threshold,
+        direction.x > threshold,
+        direction.y < -threshold,
+        direction.y > threshold,
+    )
+}
+
+/// Results of all runs of a class, weapon and policy
+#[derive(Default)]
+struct Report {
+    runs: u32,
+    wins: u32,
+    timeouts: u32,
+    /// Runs lost in each room, by room number
+    deaths: BTreeMap<usize, u32>,
+    damage: HashMap<DamageType, f32>,
+    /// Times each curse was taken, indexed by curse
+    curses: Vec<u32>,
+}
+impl Report {
+    fn print(&self, class: &str, weapon: &str, policy: Policy) {
+        let runs = self.runs.max(1) as f32;
+        println!("{class}, {weapon}, {}", policy.name());
+        println!(
+            "  win rate: {:.1}% ({} runs, {} timed out)",
+            self.wins as f32 / runs * 100.0,
+            self.runs,
+            self.timeouts
+        );
+        let deaths: Vec<String> = self
+            .deaths
+            .iter()
+            .map(|(room, count)| format!("{room}: {count}"))
+            .collect();
+        println!("  deaths by room: {}", deaths.join(", "));
+        let damage: Vec<String> = enum_iterator::all::<DamageType>()
+            .filter_map(|ty| {
+                let amt = self.damage.get(&ty)?;
+                Some(format!("{} {:.1}", ty.to_text(), amt / runs))
+            })
+            .collect();
+        println!("  damage per run: {}", damage.join(", "));
+        let curses: Vec<String> = enum_iterator::all::<ChaosCurse>()
+            .zip(self.curses.iter())
+            .map(|(curse, count)| {
+                let name = replace_pascal_case(&format!("{curse:?}"));
+                format!("{name} {:.2}", *count as f32 / runs)
+            })
+            .collect();
+        println!("  curses per run: {}", curses.join(", "));
+    }
+}
+
+fn play_runs(
+    items: &[Item],
+    enchantments: &[Enchantment],
+    class: &Class,
+    weapon: &str,
+    policy: Policy,
+    seeds: std::ops::Range<u64>,
+) -> Report {
+    let mut report = Report {
+        curses: vec![0; enum_iterator::cardinality::<ChaosCurse>()],
+        ..Default::default()
+    };
+    for seed in seeds {
+        let mut player = class_player(class);
+        *player.slot_mut(InventorySlot::Hand).unwrap() =
+            get_item_by_name(items, weapon).ok().cloned();
+        let mut sim = Simulation::new(items, enchantments, player, seed);
+        let mut steps = 0;
+        let win = loop {
+            if let Some(win) = sim.finished() {
+                break Some(win);
+            }
+            if steps >= MAX_STEPS {
+                break None;
+            }
+            let input = bot_input(&sim, policy);
+            sim.step(&input);
+            steps += 1;
+        };
+        report.runs += 1;
+        match win {
+            Some(true) => report.wins += 1,
+            Some(false) => {
+                let room = sim.dungeon_manager.total_room_index;
+                *report.deaths.entry(room).or_default() += 1;
+            }
+            None => report.timeouts += 1,
+        }
+        for (ty, amt) in sim.damage_dealt.iter() {
+            *report.damage.entry(*ty).or_default() += amt;
+        }
+        for curse in sim.player.curses().iter() {
+            report.curses[*curse as usize] += 1;
+        }
+    }
+    report
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let arg = |name: &str| {
+        let index = args.iter().position(|f| f == name)?;
+        args.get(index + 1).map(String::as_str)
+    };
+    let runs: u64 = arg("--runs").map_or(100, |f| f.parse().expect("invalid --runs"));
+    let first_seed: u64 = arg("--seed").map_or(0, |f| f.parse().expect("invalid --seed"));
+    let policies: Vec<Policy> = match arg("--policy") {
+        Some(name) => vec![
+            enum_iterator::all::<Policy>()
+                .find(|f| f.name() == name)
+                .expect("unknown --policy"),
+        ],
+        None => enum_iterator::all::<Policy>().collect(),
+    };
+
+    let items = get_items();
+    let classes = get_classes(&items).unwrap_or_else(|err| panic!("{err}"));
+    let weapons: Vec<&Item> = items
+        .iter()
+        .filter(|f| matches!(f.ty, ItemType::Held(_)))
+        .collect();
+    // every class with every weapon, played by every policy
+    let mut combinations = Vec::new();
+    for class in classes.iter() {
+        for weapon in weapons.iter() {
+            for policy in policies.iter() {
+                combinations.push((class, *weapon, *policy));
+            }
+        }
+    }
+
+    // each thread builds its own items, and takes the next combination until all are done
+    let next = AtomicUsize::new(0);
+    let reports = Mutex::new(BTreeMap::new());
+    let threads = std::thread::available_parallelism().map_or(1, |f| f.get());
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                let items = get_items();
+                let enchantments = get_enchantments();
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((class, weapon, policy)) = combinations.get(index) else {
+                        break;
+                    };
+                    let seeds = first_seed..first_seed + runs;
+                    let report =
+                        play_runs(&items, &enchantments, class, &weapon.name, *policy, seeds);
+                    reports.lock().unwrap().insert(index, report);
+                }
+            });
+        }
+    });
+
+    for (index, report) in reports.into_inner().unwrap() {
+        let (class, weapon, policy) = combinations[index];
+        report.print(class.0, &weapon.name, policy);
+    }
+}
@@ -0,0 +1,830 @@
+//! Game content loaded from the RON files in `assets/data`, and the same files in mods.
+//!
+//! Files are parsed in to the `*Data` types here, then validated and converted in to the
+//! types the game uses. Errors name the file, the entry and the field that's wrong.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+
+use macroquad::color::Color;
+use serde::{Deserialize, de::DeserializeOwned};
+
+use crate::{
+    assets::SheetId,
+    dungeon::{LAYOUT_GROUPS, World},
+    enemy::{
+        DeathEffect, EnemyMovement, EnemyPhase, EnemyType, PhaseEndCondition, ProjectileFiring,
+        StatusEffectType,
+    },
+    items::{Ability, ActiveItem, ChargeProfile, Enchantment, GIFT_ID, Item, ItemType, Weapon},
+    mods,
+    player::{OnHitTrigger, Stats},
+    projectiles::{DamageType, Projectile, get_projectile},
+};
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+struct ItemData {
+    name: String,
+    id: u16,
+    ty: ItemTypeData,
+    sprite: (f32, f32),
+    #[serde(default)]
+    stats: StatsData,
+    #[serde(skip)]
+    sheet: SheetId,
+}
+
+#[derive(Deserialize, Clone)]
+enum ItemTypeData {
+    Helmet,
+    Chestplate,
+    Talisman,
+    Held {
+        projectile: String,
+        #[serde(default)]
+        charge: Option<ChargeData>,
+    },
+    Active {
+        ability: AbilityData,
+        cooldown: f32,
+    },
+}
+
+/// [Ability] as written in data files
+#[derive(Deserialize, Clone)]
+enum AbilityData {
+    Throw {
+        projectile: String,
+        damage: BTreeMap<String, f32>,
+    },
+    Blink {
+        distance: f32,
+    },
+    Shield {
+        frames: u16,
+    },
+    Summon {
+        projectile: String,
+        damage: BTreeMap<String, f32>,
+        count: u8,
+    },
+}
+impl AbilityData {
+    fn to_ability(&self) -> Result<Ability, String> {
+        Ok(match self {
+            AbilityData::Throw {
+                projectile: name,
+                damage,
+            } => Ability::Throw(
+                projectile(name).map_err(|err| format!("projectile: {err}"))?,
+                damage_map(damage).map_err(|err| format!("damage: {err}"))?,
+            ),
+            AbilityData::Blink { distance } => Ability::Blink(*distance),
+            AbilityData::Shield { frames } => Ability::Shield(*frames),
+            AbilityData::Summon {
+                projectile: name,
+                damage,
+                count,
+            } => {
+                if *count == 0 {
+                    return Err("count: must be at least 1".into());
+                }
+                Ability::Summon(
+                    projectile(name).map_err(|err| format!("projectile: {err}"))?,
+                    damage_map(damage).map_err(|err| format!("damage: {err}"))?,
+                    *count,
+                )
+            }
+        })
+    }
+}
+
+/// [ChargeProfile] as written in data files. Multipliers left out stay at 1
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+struct ChargeData {
+    frames: u16,
+    #[serde(default = "default_multiplier")]
+    damage: f32,
+    #[serde(default = "default_multiplier")]
+    speed: f32,
+    #[serde(default = "default_multiplier")]
+    size: f32,
+    #[serde(default = "default_multiplier")]
+    move_speed: f32,
+}
+impl ChargeData {
+    fn to_profile(&self) -> Result<ChargeProfile, String> {
+        if self.frames == 0 {
+            return Err("frames: must be at least 1".into());
+        }
+        for (name, multiplier) in [
+            ("damage", self.damage),
+            ("speed", self.speed),
+            ("size", self.size),
+            ("move_speed", self.move_speed),
+        ] {
+            if multiplier < 0.0 {
+                return Err(format!("{name}: can't be negative"));
+            }
+        }
+        Ok(ChargeProfile {
+            frames: self.frames,
+            damage: self.damage,
+            speed: self.speed,
+            size: self.size,
+            move_speed: self.move_speed,
+        })
+    }
+}
+
+fn default_multiplier() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+struct EnchantmentData {
+    name: String,
+    id: u16,
+    sprite: (f32, f32),
+    #[serde(default)]
+    stats: StatsData,
+    #[serde(skip)]
+    sheet: SheetId,
+}
+
+/// [Stats] as written in data files. Damage types are written as in [DamageType::to_text].
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+struct StatsData {
+    move_speed: f32,
+    move_speed_mod: f32,
+    attack_delay: f32,
+    attack_delay_mod: f32,
+    roll_delay: f32,
+    roll_delay_mod: f32,
+    cooldown_mod: f32,
+    impact_damage_mod: f32,
+    crit_chance: f32,
+    crit_damage_mod: f32,
+    pierce: u16,
+    bounces: u16,
+    projectiles: u16,
+    spread: f32,
+    burst: u16,
+    max_lives: u16,
+    damage: BTreeMap<String, f32>,
+    damage_modifiers: BTreeMap<String, f32>,
+    on_hit_effects: Vec<OnHitEffectData>,
+    /// Chance of each status effect, by the damage type that applies it
+    status_chances: BTreeMap<String, BTreeMap<String, f32>>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+struct OnHitEffectData {
+    /// Damage type that has to be dealt for the effect to trigger, "crit" for critical hits,
+    /// or None for any hit
+    #[serde(default)]
+    trigger: Option<String>,
+    projectile: String,
+    damage: BTreeMap<String, f32>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+struct EnemyTypeData {
+    name: String,
+    speed: f32,
+    max_health: f32,
+    /// Fraction of each damage type the enemy resists. Negative for weaknesses.
+    #[serde(default)]
+    resistances: BTreeMap<String, f32>,
+    /// Knockback is divided by weight
+    #[serde(default = "default_weight")]
+    weight: f32,
+    /// Knockback speed a hit has to be above to stagger the enemy
+    #[serde(default)]
+    poise: f32,
+    phases: Vec<EnemyPhaseData>,
+    #[serde(default)]
+    on_death: Vec<DeathEffectData>,
+    #[serde(skip)]
+    sheet: SheetId,
+}
+
+#[derive(Deserialize, Clone)]
+enum DeathEffectData {
+    Split { enemy: String, count: u8 },
+    Explode { projectile: String, count: u8 },
+    Hazard { projectile: String },
+    Heal { radius: f32, amount: f32 },
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+struct EnemyPhaseData {
+    sprite: (f32, f32),
+    frames: usize,
+    movement: EnemyMovementData,
+    #[serde(default)]
+    firing: ProjectileFiringData,
+    #[serde(default)]
+    end: PhaseEndConditionData,
+}
+
+#[derive(Deserialize, Clone)]
+enum EnemyMovementData {
+    Chase,
+    Wander { face_player: bool },
+    Still,
+    Forwards,
+}
+
+#[derive(Deserialize, Default, Clone)]
+enum ProjectileFiringData {
+    #[default]
+    None,
+    Forwards {
+        projectile: String,
+        delay: u8,
+    },
+    Around {
+        projectile: String,
+        delay: u8,
+        count: u8,
+    },
+}
+
+#[derive(Deserialize, Default, Clone)]
+enum PhaseEndConditionData {
+    #[default]
+    None,
+    SingleFrame,
+    Frames(u32),
+    HealthUnder(f32),
+    Collision,
+    PlayerDistance(f32),
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+struct WorldData {
+    name: String,
+    background_color: u32,
+    tiles: f32,
+    rooms: usize,
+    light: Vec<String>,
+    heavy: Vec<String>,
+    ranged: Vec<String>,
+    other: Vec<String>,
+    miniboss: Vec<String>,
+    #[serde(skip)]
+    sheet: SheetId,
+}
+
+impl ItemData {
+    fn to_item(&self) -> Result<Item, String> {
+        if self.id == GIFT_ID {
+            return Err(format!("id: {GIFT_ID} is reserved for the gift"));
+        }
+        let ty = match &self.ty {
+            ItemTypeData::Helmet => ItemType::Helmet,
+            ItemTypeData::Chestplate => ItemType::Chestplate,
+            ItemTypeData::Talisman => ItemType::Talisman,
+            ItemTypeData::Held {
+                projectile: name,
+                charge,
+            } => ItemType::Held(Box::new(Weapon {
+                projectile: projectile(name).map_err(|err| format!("ty.projectile: {err}"))?,
+                charge: charge
+                    .as_ref()
+                    .map(|f| f.to_profile())
+                    .transpose()
+                    .map_err(|err| format!("ty.charge.{err}"))?,
+            })),
+            ItemTypeData::Active { ability, cooldown } => {
+                if !(cooldown.is_finite() && *cooldown > 0.0) {
+                    return Err("ty.cooldown: must be a finite number above 0".into());
+                }
+                ItemType::Active(Box::new(ActiveItem {
+                    ability: ability
+                        .to_ability()
+                        .map_err(|err| format!("ty.ability.{err}"))?,
+                    cooldown: *cooldown,
+                }))
+            }
+        };
+        Ok(Item {
+            id: self.id,
+            name: self.name.clone(),
+            ty,
+            sheet: self.sheet,
+            sprite_x: self.sprite.0,
+            sprite_y: self.sprite.1,
+            internal_stats: self.stats.to_stats()?,
+            enchantment: None,
+        })
+    }
+}
+
+impl EnemyTypeData {
+    /// The enemy type without its death effects, which need the other types to be built first
+    fn to_enemy_type(&self) -> Result<EnemyType, String> {
+        let numbers = [
+            ("max_health", self.max_health),
+            ("speed", self.speed),
+            ("weight", self.weight),
+            ("poise", self.poise),
+        ];
+        for (field, value) in numbers {
+            // ron reads "inf" and "NaN" as numbers too
+            let err = if !value.is_finite() {
+                "must be a finite number"
+            } else if value < 0.0 {
+                "can't be negative"
+            } else if value == 0.0 && !["speed", "poise"].contains(&field) {
+                // speed is 0 for enemies that don't move, and poise for enemies any
+                // knockback staggers
+                "must be above 0"
+            } else {
+                continue;
+            };
+            return Err(format!("{field}: {err}"));
+        }
+        Ok(EnemyType {
+            name: self.name.clone(),
+            speed: self.speed,
+            phases: self.to_phases()?,
+            max_health: self.max_health,
+            resistances: self
+                .to_resistances()
+                .map_err(|err| format!("resistances: {err}"))?,
+            weight: self.weight,
+            poise: self.poise,
+            on_death: Vec::new(),
+            sheet: self.sheet,
+        })
+    }
+    fn to_phases(&self) -> Result<Vec<EnemyPhase>, String> {
+        if self.phases.is_empty() {
+            return Err("phases: needs at least one phase".into());
+        }
+        self.phases
+            .iter()
+            .enumerate()
+            .map(|(index, phase)| {
+                phase
+                    .to_phase()
+                    .map_err(|err| format!("phases[{index}].{err}"))
+            })
+            .collect()
+    }
+    fn to_resistances(&self) -> Result<HashMap<DamageType, f32>, String> {
+        let resistances = damage_map(&self.resistances)?;
+        for (ty, amt) in resistances.iter() {
+            if *amt > 1.0 {
+                return Err(format!("{}: must be at most 1", ty.to_text()));
+            }
+        }
+        Ok(resistances)
+    }
+}
+
+impl WorldData {
+    fn to_world(&self, enemy_types: &[EnemyType]) -> Result<World, String> {
+        if self.rooms >= LAYOUT_GROUPS {
+            return Err(format!("rooms: can be at most {}", LAYOUT_GROUPS - 1));
+        }
+        let tier = |field: &str, names: &[String]| {
+            if names.is_empty() {
+                return Err(format!("{field}: needs at least one enemy"));
+            }
+            names
+                .iter()
+                .map(|name| {
+                    enemy_types
+                        .iter()
+                        .find(|f| f.name == *name)
+                        .cloned()
+                        .ok_or(format!("{field}: unknown enemy '{name}'"))
+                })
+                .collect()
+        };
+        Ok(World {
+            name: self.name.clone(),
+            background_color: Color::from_hex(self.background_color),
+            sheet: self.sheet,
+            tiles: self.tiles,
+            rooms: self.rooms,
+            light: tier("light", &self.light)?,
+            heavy: tier("heavy", &self.heavy)?,
+            ranged: tier("ranged", &self.ranged)?,
+            other: tier("other", &self.other)?,
+            miniboss: tier("miniboss", &self.miniboss)?,
+        })
+    }
+}
+
+impl EnemyPhaseData {
+    fn to_phase(&self) -> Result<EnemyPhase, String> {
+        if self.frames == 0 {
+            return Err("frames: must be at least 1".into());
+        }
+        let movement = match self.movement {
+            EnemyMovementData::Chase => EnemyMovement::Chase,
+            EnemyMovementData::Wander { face_player } => EnemyMovement::Wander(face_player),
+            EnemyMovementData::Still => EnemyMovement::Still,
+            EnemyMovementData::Forwards => EnemyMovement::Fowards,
+        };
+        let firing_projectile =
+            |name: &str| projectile(name).map_err(|err| format!("firing.projectile: {err}"));
+        if let ProjectileFiringData::Forwards { delay: 0, .. }
+        | ProjectileFiringData::Around { delay: 0, .. } = self.firing
+        {
+            return Err("firing.delay: must be at least 1".into());
+        }
+        let firing = match &self.firing {
+            ProjectileFiringData::None => ProjectileFiring::None,
+            ProjectileFiringData::Forwards { projectile, delay } => {
+                ProjectileFiring::Forwards(firing_projectile(projectile)?, *delay)
+            }
+            ProjectileFiringData::Around {
+                projectile,
+                delay,
+                count,
+            } => ProjectileFiring::Around(firing_projectile(projectile)?, *delay, *count),
+        };
+        let end = match self.end {
+            PhaseEndConditionData::None => PhaseEndCondition::None,
+            PhaseEndConditionData::SingleFrame => PhaseEndCondition::SingleFrame,
+            PhaseEndConditionData::Frames(frames) => PhaseEndCondition::Frames(frames),
+            PhaseEndConditionData::HealthUnder(amt) => PhaseEndCondition::HealthUnder(amt),
+            PhaseEndConditionData::Collision => PhaseEndCondition::Collision,
+            PhaseEndConditionData::PlayerDistance(amt) => PhaseEndCondition::PlayerDistance(amt),
+        };
+        Ok(EnemyPhase {
+            movement,
+            firing,
+            sprite_x: self.sprite.0,
+            sprite_y: self.sprite.1,
+            frames: self.frames,
+            end,
+        })
+    }
+}
+
+impl StatsData {
+    fn to_stats(&self) -> Result<Stats, String> {
+        let mut on_hit_effects: HashMap<OnHitTrigger, Vec<_>> = HashMap::new();
+        for (index, effect) in self.on_hit_effects.iter().enumerate() {
+            let field = format!("stats.on_hit_effects[{index}]");
+            let trigger = match effect.trigger.as_deref() {
+                None => OnHitTrigger::Hit,
+                Some("crit") => OnHitTrigger::Crit,
+                Some(ty) => OnHitTrigger::Damage(
+                    damage_type(ty).map_err(|err| format!("{field}.trigger: {err}"))?,
+                ),
+            };
+            let projectile = projectile(&effect.projectile)
+                .map_err(|err| format!("{field}.projectile: {err}"))?;
+            let damage =
+                damage_map(&effect.damage).map_err(|err| format!("{field}.damage: {err}"))?;
+            on_hit_effects
+                .entry(trigger)
+                .or_default()
+                .push((projectile, damage));
+        }
+        Ok(Stats {
+            move_speed: self.move_speed,
+            move_speed_mod: self.move_speed_mod,
+            attack_delay: self.attack_delay,
+            attack_delay_mod: self.attack_delay_mod,
+            roll_delay: self.roll_delay,
+            roll_delay_mod: self.roll_delay_mod,
+            cooldown_mod: self.cooldown_mod,
+            impact_damage_mod: self.impact_damage_mod,
+            crit_chance: self.crit_chance,
+            crit_damage_mod: self.crit_damage_mod,
+            pierce: self.pierce,
+            bounces: self.bounces,
+            projectiles: self.projectiles,
+            spread: self.spread,
+            burst: self.burst,
+            max_lives: self.max_lives,
+            lives: self.max_lives,
+            damage: damage_map(&self.damage).map_err(|err| format!("stats.damage: {err}"))?,
+            damage_modifiers: damage_map(&self.damage_modifiers)
+                .map_err(|err| format!("stats.damage_modifiers: {err}"))?,
+            on_hit_effects,
+            status_chances: self
+                .status_chances
+                .iter()
+                .map(|(ty, chances)| {
+                    let field = format!("stats.status_chances.{ty}");
+                    let ty = damage_type(ty).map_err(|err| format!("{field}: {err}"))?;
+                    let chances = chances
+                        .iter()
+                        .map(|(effect, chance)| Ok((status_effect(effect)?, *chance)))
+                        .collect::<Result<_, String>>()
+                        .map_err(|err| format!("{field}: {err}"))?;
+                    Ok((ty, chances))
+                })
+                .collect::<Result<_, String>>()?,
+        })
+    }
+}
+
+fn damage_type(name: &str) -> Result<DamageType, String> {
+    DamageType::from_text(name).ok_or(format!("unknown damage type '{name}'"))
+}
+
+fn status_effect(name: &str) -> Result<StatusEffectType, String> {
+    StatusEffectType::from_text(name).ok_or(format!("unknown status effect '{name}'"))
+}
+
+fn damage_map(map: &BTreeMap<String, f32>) -> Result<HashMap<DamageType, f32>, String> {
+    map.iter()
+        .map(|(ty, amt)| Ok((damage_type(ty)?, *amt)))
+        .collect()
+}
+
+fn projectile(name: &str) -> Result<Projectile, String> {
+    get_projectile(name).ok_or(format!("unknown projectile '{name}'"))
+}
+
+/// Parse a list of entries. Syntax errors are reported with their position,
+/// and the name of the entry they're in if it can be found.
+fn parse<T: DeserializeOwned>(source: &str, text: &str) -> Result<Vec<T>, String> {
+    ron::from_str(text).map_err(|err| {
+        let position = format!("{source}:{}:{}", err.position.line, err.position.col);
+        match entry_name_at(text, err.position.line) {
+            Some(name) => format!("{position}: '{name}': {}", err.code),
+            None => format!("{position}: {}", err.code),
+        }
+    })
+}
+
+/// Returns the name of the entry a line is in, which is the last `name` field above it
+fn entry_name_at(text: &str, line: usize) -> Option<&str> {
+    text.lines()
+        .take(line)
+        .filter_map(|f| f.trim().strip_prefix("name:"))
+        .last()
+        .map(|f| f.trim().trim_end_matches(',').trim_matches('"'))
+}
+
+/// Check that no two entries in a file share a name
+fn check_unique_names<'a>(kind: &str, names: impl Iterator<Item = &'a str>) -> Result<(), String> {
+    let mut used = HashSet::new();
+    for name in names {
+        if !used.insert(name) {
+            return Err(format!("{kind} '{name}': name is already used"));
+        }
+    }
+    Ok(())
+}
+
+/// Check that no two entries share an id
+fn check_unique_ids<'a>(
+    kind: &str,
+    entries: impl Iterator<Item = (&'a str, u16)>,
+) -> Result<(), String> {
+    let mut used = HashSet::new();
+    for (name, id) in entries {
+        if !used.insert(id) {
+            return Err(format!("{kind} '{name}': id {id} is already used"));
+        }
+    }
+    Ok(())
+}
+
+/// A data file to load entries from
+pub struct DataFile {
+    /// Path of the file, as shown in errors
+    pub path: String,
+    pub text: String,
+    /// Name of the mod the file is from, or None for the game's own
+    pub from_mod: Option<String>,
+    /// Spritesheet the entries' sprites are on
+    pub sheet: SheetId,
+}
+
+/// An entry in a data file
+trait Entry: DeserializeOwned + Clone {
+    fn name(&self) -> &str;
+    fn set_sheet(&mut self, sheet: SheetId);
+}
+macro_rules! impl_entry {
+    ($($ty:ty),*) => {$(
+        impl Entry for $ty {
+            fn name(&self) -> &str {
+                &self.name
+            }
+            fn set_sheet(&mut self, sheet: SheetId) {
+                self.sheet = sheet;
+            }
+        }
+    )*};
+}
+impl_entry!(ItemData, EnchantmentData, EnemyTypeData, WorldData);
+
+/// Load the entries of the game's file, which comes first, and then the files of mods.
+/// A mod's entry replaces the entry with the same name, or is added after the others.
+/// Errors in the game's file are returned, while a mod's file with errors is reported and left out.
+fn load_files<T: Entry, U>(
+    kind: &str,
+    files: &[DataFile],
+    convert: impl Fn(&[T]) -> Result<Vec<U>, String>,
+) -> Result<Vec<U>, String> {
+    let parse_file = |file: &DataFile| -> Result<Vec<T>, String> {
+        let mut entries: Vec<T> = parse(&file.path, &file.text)?;
+        check_unique_names(kind, entries.iter().map(|f| f.name()))
+            .map_err(|err| format!("{}: {err}", file.path))?;
+        for entry in entries.iter_mut() {
+            entry.set_sheet(file.sheet);
+        }
+        Ok(entries)
+    };
+    let (builtin, mod_files) = files.split_first().expect("the game's file comes first");
+    let mut entries = parse_file(builtin)?;
+    let mut loaded = convert(&entries).map_err(|err| format!("{}: {err}", builtin.path))?;
+    // mod that last changed each entry
+    let mut changed_by: HashMap<String, &str> = HashMap::new();
+    for file in mod_files {
+        let mod_name = file.from_mod.as_deref().unwrap_or_default();
+        let result = parse_file(file).and_then(|new| {
+            let mut merged = entries.clone();
+            for entry in new.iter() {
+                match merged.iter_mut().find(|f| f.name() == entry.name()) {
+                    Some(old) => *old = entry.clone(),
+                    None => merged.push(entry.clone()),
+                }
+            }
+            let merged_loaded = convert(&merged).map_err(|err| format!("{}: {err}", file.path))?;
+            Ok((new, merged, merged_loaded))
+        });
+        let (new, merged, merged_loaded) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                mods::report(format!("{err}, so the file is left out"));
+                continue;
+            }
+        };
+        for entry in new.iter() {
+            if let Some(other) = changed_by.insert(entry.name().to_string(), mod_name) {
+                mods::report(format!(
+                    "mods '{other}' and '{mod_name}' both change {kind} '{}', '{mod_name}' is used",
+                    entry.name()
+                ));
+            }
+        }
+        entries = merged;
+        loaded = merged_loaded;
+    }
+    Ok(loaded)
+}
+
+/// Load items from the game's data file and mods' files
+pub fn load_items(files: &[DataFile]) -> Result<Vec<Item>, String> {
+    load_files("item", files, |entries: &[ItemData]| {
+        check_unique_ids("item", entries.iter().map(|f| (f.name.as_str(), f.id)))?;
+        entries
+            .iter()
+            .map(|entry| {
+                entry
+                    .to_item()
+                    .map_err(|err| format!("item '{}': {err}", entry.name))
+            })
+            .collect()
+    })
+}
+
+/// Load enchantments from the game's data file and mods' files
+pub fn load_enchantments(files: &[DataFile]) -> Result<Vec<Enchantment>, String> {
+    load_files("enchantment", files, |entries: &[EnchantmentData]| {
+        check_unique_ids(
+            "enchantment",
+            entries.iter().map(|f| (f.name.as_str(), f.id)),
+        )?;
+        entries
+            .iter()
+            .map(|entry| {
+                let stats = entry
+                    .stats
+                    .to_stats()
+                    .map_err(|err| format!("enchantment '{}': {err}", entry.name))?;
+                Ok(Enchantment {
+                    id: entry.id,
+                    name: entry.name.clone(),
+                    stats,
+                    sheet: entry.sheet,
+                    sprite_x: entry.sprite.0,
+                    sprite_y: entry.sprite.1,
+                })
+            })
+            .collect()
+    })
+}
+
+/// Load enemy types from the game's data file and mods' files
+pub fn load_enemy_types(files: &[DataFile]) -> Result<Vec<EnemyType>, String> {
+    load_files("enemy", files, |entries: &[EnemyTypeData]| {
+        let types: Vec<EnemyType> = entries
+            .iter()
+            .map(|entry| {
+                entry
+                    .to_enemy_type()
+                    .map_err(|err| format!("enemy '{}': {err}", entry.name))
+            })
+            .collect::<Result<_, String>>()?;
+        // death effects are added once every type is built, as enemies can split in to types
+        // further down the file
+        let mut resolved = vec![None; entries.len()];
+        for index in 0..entries.len() {
+            with_death_effects(index, entries, &types, &mut resolved, &mut Vec::new())?;
+        }
+        Ok(resolved
+            .into_iter()
+            .map(|f| Arc::unwrap_or_clone(f.unwrap()))
+            .collect())
+    })
+}
+
+/// The enemy type at `index` with its death effects. They're only worked out the first time,
+/// and the types split in to are shared, so chains of splits load quickly. `splitting` holds
+/// the types being worked out, so a type that splits back in to one of them is an error.
+fn with_death_effects(
+    index: usize,
+    entries: &[EnemyTypeData],
+    types: &[EnemyType],
+    resolved: &mut [Option<Arc<EnemyType>>],
+    splitting: &mut Vec<usize>,
+) -> Result<Arc<EnemyType>, String> {
+    if let Some(ty) = &resolved[index] {
+        return Ok(ty.clone());
+    }
+    let entry = &entries[index];
+    splitting.push(index);
+    let mut on_death = Vec::new();
+    for (effect_index, effect) in entry.on_death.iter().enumerate() {
+        let field = |err: String| format!("enemy '{}': on_death[{effect_index}].{err}", entry.name);
+        on_death.push(match effect {
+            DeathEffectData::Split { enemy, count } => {
+                let split = entries
+                    .iter()
+                    .position(|f| f.name == *enemy)
+                    .ok_or(field(format!("enemy: unknown enemy '{enemy}'")))?;
+                if splitting.contains(&split) {
+                    return Err(field(format!("enemy: '{enemy}' splits in to itself")));
+                }
+                let ty = with_death_effects(split, entries, types, resolved, splitting)?;
+                DeathEffect::Split(ty, *count)
+            }
+            DeathEffectData::Explode {
+                projectile: name,
+                count,
+            } => DeathEffect::Explode(
+                projectile(name).map_err(|err| field(format!("projectile: {err}")))?,
+                *count,
+            ),
+            DeathEffectData::Hazard { projectile: name } => DeathEffect::Hazard(
+                projectile(name).map_err(|err| field(format!("projectile: {err}")))?,
+            ),
+            DeathEffectData::Heal { radius, amount } => DeathEffect::Heal(*radius, *amount),
+        });
+    }
+    splitting.pop();
+    let ty = Arc::new(EnemyType {
+        on_death,
+        ..types[index].clone()
+    });
+    resolved[index] = Some(ty.clone());
+    Ok(ty)
+}
+
+/// Load worlds from the game's data file and mods' files, in the order they're played.
+/// Enemies are looked up by name in `enemy_types`.
+pub fn load_worlds(files: &[DataFile], enemy_types: &[EnemyType]) -> Result<Vec<World>, String> {
+    load_files("world", files, |entries: &[WorldData]| {
+        if entries.is_empty() {
+            return Err("needs at least one world".into());
+        }
+        entries
+            .iter()
+            .map(|entry| {
+                entry
+                    .to_world(enemy_types)
+                    .map_err(|err| format!("world '{}': {err}", entry.name))
+            })
+            .collect()
+    })
+}
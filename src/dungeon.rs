@@ -1,18 +1,24 @@
-use std::{collections::HashMap, sync::LazyLock};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, RwLock},
+};
 
 use hashmap_macro::hashmap;
 use image::{GenericImageView, Rgba};
 use macroquad::prelude::*;
 
 use crate::{
+    assets::{SheetId, asset},
     enemy::{Enemy, EnemyTier, EnemyType},
+    mods,
+    rng::{Rng, RunRng},
     utils::*,
 };
 
 pub struct DungeonManager {
     pub worlds: Vec<&'static World>,
     pub world_index: usize,
-    room_index: usize,
+    pub room_index: usize,
     pub total_room_index: usize,
 }
 impl DungeonManager {
@@ -24,11 +30,11 @@ impl DungeonManager {
             total_room_index: 0,
         }
     }
-    pub fn spawn_room(&mut self) -> Option<Vec<Enemy>> {
+    pub fn spawn_room(&mut self, rng: &mut RunRng) -> Option<Vec<Enemy>> {
         self.room_index += 1;
         self.total_room_index += 1;
-        // move to next world if all 12 levels complete or if first 10 is complete in the first world
-        if self.room_index > 12 || (self.world_index == 0 && self.room_index > 10) {
+        // move to next world once all of this world's rooms are complete
+        if self.room_index > self.worlds[self.world_index].rooms {
             self.room_index = 0;
             self.world_index += 1;
             if self.world_index >= self.worlds.len() {
@@ -36,19 +42,23 @@ impl DungeonManager {
                 return None;
             }
         }
-        fn get_types(dungeon_manager: &DungeonManager) -> HashMap<EnemyTier, &'static EnemyType> {
+        fn get_types(
+            dungeon_manager: &DungeonManager,
+            rng: &mut Rng,
+        ) -> HashMap<EnemyTier, &'static EnemyType> {
             hashmap!(
-                EnemyTier::Light => select_random(&dungeon_manager.worlds[dungeon_manager.world_index].light),
-                EnemyTier::Heavy => select_random(&dungeon_manager.worlds[dungeon_manager.world_index].heavy),
-                EnemyTier::Ranged =>  select_random(&dungeon_manager.worlds[dungeon_manager.world_index].ranged),
-                EnemyTier::Miniboss => select_random(&dungeon_manager.worlds[dungeon_manager.world_index].miniboss),
+                EnemyTier::Light => select_random(rng, &dungeon_manager.worlds[dungeon_manager.world_index].light),
+                EnemyTier::Heavy => select_random(rng, &dungeon_manager.worlds[dungeon_manager.world_index].heavy),
+                EnemyTier::Ranged =>  select_random(rng, &dungeon_manager.worlds[dungeon_manager.world_index].ranged),
+                EnemyTier::Miniboss => select_random(rng, &dungeon_manager.worlds[dungeon_manager.world_index].miniboss),
             )
         }
-        let mut types = get_types(self);
+        let mut types = get_types(self, &mut rng.layouts);
 
         let layout_group_index = self.room_index;
-        let layout_group = &LAYOUTS[layout_group_index];
-        let layout = &layout_group[rand::gen_range(0, layout_group.len())];
+        let layouts = LAYOUTS.read().unwrap();
+        let layout_group = &layouts[layout_group_index];
+        let layout = &layout_group[rng.layouts.gen_range(0, layout_group.len())];
         let mut enemies = Vec::new();
         let mut last_row = -1.0;
         for (index, value) in layout.iter().enumerate() {
@@ -58,19 +68,19 @@ impl DungeonManager {
             let x = (index % TILES_WIDTH as usize) as f32 * 16.0 + 8.0;
             let y = (index / TILES_WIDTH as usize) as f32 * 16.0 + 8.0;
             if y != last_row {
-                types = get_types(self);
+                types = get_types(self, &mut rng.layouts);
             }
             last_row = y;
             let ty = types[value];
-            let enemy = Enemy::new(ty, Vec2::new(x, y), 0);
+            let enemy = Enemy::new(ty, Vec2::new(x, y), 0, &mut rng.ai);
             enemies.push(enemy);
         }
-        if rand::gen_range(0, 100) < OTHER_CHANCE {
+        if rng.layouts.gen_range(0, 100) < OTHER_CHANCE {
             // spawn an "other" in top left and right corners
-            let ty = select_random(&self.worlds[self.world_index].other);
+            let ty = select_random(&mut rng.layouts, &self.worlds[self.world_index].other);
             let positions = [Vec2::new(8.0, 8.0), Vec2::new(SCREEN_WIDTH - 8.0, 8.0)];
             for position in positions {
-                let enemy = Enemy::new(ty, position, 0);
+                let enemy = Enemy::new(ty, position, 0, &mut rng.ai);
                 enemies.push(enemy);
             }
         }
@@ -80,7 +90,14 @@ impl DungeonManager {
 }
 
 pub struct World {
+    pub name: String,
     pub background_color: Color,
+    /// Sheet the world's tiles are on
+    pub sheet: SheetId,
+    /// Row of the world spritesheet the world's tiles are on
+    pub tiles: f32,
+    /// Number of rooms before moving on to the next world
+    pub rooms: usize,
     pub light: Vec<EnemyType>,
     pub heavy: Vec<EnemyType>,
     pub ranged: Vec<EnemyType>,
@@ -88,46 +105,80 @@ pub struct World {
     pub miniboss: Vec<EnemyType>,
 }
 
-pub static LAYOUTS: LazyLock<[Vec<Layout>; 14]> = LazyLock::new(|| {
-    let layouts: [&[u8]; _] = [
-        include_bytes!("../assets/layouts/0.png"),
-        include_bytes!("../assets/layouts/1.png"),
-        include_bytes!("../assets/layouts/2.png"),
-        include_bytes!("../assets/layouts/3.png"),
-        include_bytes!("../assets/layouts/4.png"),
-        include_bytes!("../assets/layouts/miniboss.png"),
-        include_bytes!("../assets/layouts/5.png"),
-        include_bytes!("../assets/layouts/6.png"),
-        include_bytes!("../assets/layouts/7.png"),
-        include_bytes!("../assets/layouts/8.png"),
-        include_bytes!("../assets/layouts/9.png"),
-        include_bytes!("../assets/layouts/10.png"),
-        include_bytes!("../assets/layouts/11.png"),
-        include_bytes!("../assets/layouts/12.png"),
-    ];
+/// Number of layout groups. A world's room number picks the group its layouts come from.
+pub const LAYOUT_GROUPS: usize = 14;
+
+pub static LAYOUTS: LazyLock<RwLock<[Vec<Layout>; LAYOUT_GROUPS]>> =
+    LazyLock::new(|| RwLock::new(load_layouts().unwrap_or_else(|err| panic!("{err}"))));
+
+/// Load the layouts in `assets/layouts` again, keeping the old ones if there's an error
+pub fn reload_layouts() -> Result<(), String> {
+    *LAYOUTS.write().unwrap() = load_layouts()?;
+    Ok(())
+}
 
-    std::array::from_fn(|i| {
-        let image = image::load_from_memory(layouts[i]).unwrap();
-        assert_eq!(image.width(), TILES_WIDTH);
-        assert!(image.height() % TILES_HEIGHT == 0);
-        let mut vec = Vec::new();
-        for layout_index in 0..image.height() / TILES_HEIGHT {
-            vec.push(std::array::from_fn(|i| {
-                let y = i as u32 / TILES_WIDTH + layout_index * TILES_HEIGHT;
-                let x = i as u32 % TILES_WIDTH;
-                let value = image.get_pixel(x, y);
-                match value {
-                    Rgba([0, 0, 0, 255]) => None,
-                    Rgba([255, 255, 255, 255]) => Some(EnemyTier::Light),
-                    Rgba([0, 0, 255, 255]) => Some(EnemyTier::Heavy),
-                    Rgba([255, 255, 0, 255]) => Some(EnemyTier::Ranged),
-                    Rgba([255, 0, 0, 255]) => Some(EnemyTier::Miniboss),
-                    _ => panic!(),
+fn load_layouts() -> Result<[Vec<Layout>; LAYOUT_GROUPS], String> {
+    let files = [
+        ("layouts/0.png", asset!(try "layouts/0.png")),
+        ("layouts/1.png", asset!(try "layouts/1.png")),
+        ("layouts/2.png", asset!(try "layouts/2.png")),
+        ("layouts/3.png", asset!(try "layouts/3.png")),
+        ("layouts/4.png", asset!(try "layouts/4.png")),
+        ("layouts/miniboss.png", asset!(try "layouts/miniboss.png")),
+        ("layouts/5.png", asset!(try "layouts/5.png")),
+        ("layouts/6.png", asset!(try "layouts/6.png")),
+        ("layouts/7.png", asset!(try "layouts/7.png")),
+        ("layouts/8.png", asset!(try "layouts/8.png")),
+        ("layouts/9.png", asset!(try "layouts/9.png")),
+        ("layouts/10.png", asset!(try "layouts/10.png")),
+        ("layouts/11.png", asset!(try "layouts/11.png")),
+        ("layouts/12.png", asset!(try "layouts/12.png")),
+    ];
+    let mut groups = Vec::new();
+    for (name, bytes) in files {
+        // a mod's layouts replace the whole group, unless they can't be loaded
+        if let Some((mod_name, bytes)) = mods::override_file(name) {
+            match load_layout_group(&bytes) {
+                Ok(group) => {
+                    groups.push(group);
+                    continue;
                 }
-            }));
+                Err(err) => mods::report(format!("mods/{mod_name}/{name}: {err}")),
+            }
         }
-        vec
-    })
-});
+        groups.push(load_layout_group(&bytes?).map_err(|err| format!("{name}: {err}"))?);
+    }
+    Ok(groups
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("there's a group per file")))
+}
+
+/// Load the layouts in an image, which are stacked on top of each other
+fn load_layout_group(bytes: &[u8]) -> Result<Vec<Layout>, String> {
+    let image = image::load_from_memory(bytes).map_err(|err| err.to_string())?;
+    if image.width() != TILES_WIDTH || !image.height().is_multiple_of(TILES_HEIGHT) {
+        return Err(format!(
+            "size must be {TILES_WIDTH} wide and a multiple of {TILES_HEIGHT} high"
+        ));
+    }
+    let mut vec = Vec::new();
+    for layout_index in 0..image.height() / TILES_HEIGHT {
+        let mut layout = [const { None }; TILES_WIDTH as usize * TILES_HEIGHT as usize];
+        for (i, tile) in layout.iter_mut().enumerate() {
+            let y = i as u32 / TILES_WIDTH + layout_index * TILES_HEIGHT;
+            let x = i as u32 % TILES_WIDTH;
+            *tile = match image.get_pixel(x, y) {
+                Rgba([0, 0, 0, 255]) => None,
+                Rgba([255, 255, 255, 255]) => Some(EnemyTier::Light),
+                Rgba([0, 0, 255, 255]) => Some(EnemyTier::Heavy),
+                Rgba([255, 255, 0, 255]) => Some(EnemyTier::Ranged),
+                Rgba([255, 0, 0, 255]) => Some(EnemyTier::Miniboss),
+                _ => return Err(format!("unknown color at ({x}, {y})")),
+            };
+        }
+        vec.push(layout);
+    }
+    Ok(vec)
+}
 
 pub type Layout = [Option<EnemyTier>; TILES_WIDTH as usize * TILES_HEIGHT as usize];
@@ -1,7 +1,110 @@
+use std::{collections::HashMap, sync::Arc};
+
+use enum_iterator::Sequence;
 use macroquad::prelude::*;
 
-use crate::{assets::Assets, projectiles::Projectile, utils::*};
+use crate::{
+    assets::{Assets, SheetId},
+    projectiles::{DamageType, Projectile},
+    rng::Rng,
+    utils::*,
+};
+
+/// Frames between each time damage over time effects deal damage
+pub const STATUS_TICK_FRAMES: u32 = 30;
+/// Factor knockback speed is multiplied by each frame
+const KNOCKBACK_FRICTION: f32 = 0.8;
+/// Knockback speed below which an enemy stops sliding
+const KNOCKBACK_STOP_SPEED: f32 = 0.1;
+/// Knockback speed an enemy has to hit a wall at to take impact damage
+pub const SLAM_SPEED: f32 = 1.0;
+/// Frames the critical hit burst is shown for
+pub const CRIT_FRAMES: u8 = 10;
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Sequence)]
+pub enum StatusEffectType {
+    /// Deals fire damage over time, more with each stack
+    Burn,
+    /// Halves movement and attack speed
+    Freeze,
+    /// Deals unholy damage over time, more with each stack, for longer than burn
+    Poison,
+    /// Stops the enemy from moving and attacking
+    Stun,
+}
+impl StatusEffectType {
+    pub fn to_text(self) -> &'static str {
+        match self {
+            StatusEffectType::Burn => "burn",
+            StatusEffectType::Freeze => "freeze",
+            StatusEffectType::Poison => "poison",
+            StatusEffectType::Stun => "stun",
+        }
+    }
+    pub fn from_text(text: &str) -> Option<Self> {
+        enum_iterator::all::<StatusEffectType>().find(|f| f.to_text() == text)
+    }
+    /// Frames the effect lasts for, reset each time it's applied
+    pub fn duration(self) -> u16 {
+        match self {
+            StatusEffectType::Burn => 180,
+            StatusEffectType::Freeze => 120,
+            StatusEffectType::Poison => 300,
+            StatusEffectType::Stun => 30,
+        }
+    }
+    pub fn max_stacks(self) -> u8 {
+        match self {
+            StatusEffectType::Burn => 3,
+            StatusEffectType::Poison => 5,
+            StatusEffectType::Freeze | StatusEffectType::Stun => 1,
+        }
+    }
+    /// Damage dealt per stack every [STATUS_TICK_FRAMES] frames
+    pub fn tick_damage(self) -> Option<(DamageType, f32)> {
+        match self {
+            StatusEffectType::Burn => Some((DamageType::Fire, 1.0)),
+            StatusEffectType::Poison => Some((DamageType::Unholy, 0.5)),
+            StatusEffectType::Freeze | StatusEffectType::Stun => None,
+        }
+    }
+    /// Factor of the enemy's movement speed
+    pub fn move_multiplier(self) -> f32 {
+        match self {
+            StatusEffectType::Freeze => 0.5,
+            StatusEffectType::Stun => 0.0,
+            StatusEffectType::Burn | StatusEffectType::Poison => 1.0,
+        }
+    }
+    /// Factor of how fast the enemy's attack counter goes down
+    pub fn attack_speed_multiplier(self) -> f32 {
+        match self {
+            StatusEffectType::Freeze => 0.5,
+            StatusEffectType::Stun => 0.0,
+            StatusEffectType::Burn | StatusEffectType::Poison => 1.0,
+        }
+    }
+    fn color(self) -> Color {
+        match self {
+            StatusEffectType::Burn => Color::from_hex(0xda2424),
+            StatusEffectType::Freeze => Color::from_hex(0x5ee9e9),
+            StatusEffectType::Poison => Color::from_hex(0x08b23b),
+            StatusEffectType::Stun => Color::from_hex(0xf6e36c),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct StatusEffect {
+    pub ty: StatusEffectType,
+    pub stacks: u8,
+    /// Frames left until the effect wears off
+    pub frames: u16,
+    /// Frames since the effect was first applied, which isn't reset by applying it again
+    pub age: u32,
+}
+
+#[derive(Clone)]
 pub enum EnemyMovement {
     /// Enemy chases player
     Chase,
@@ -13,13 +116,13 @@ pub enum EnemyMovement {
     Fowards,
 }
 
+#[derive(Clone)]
 pub enum ProjectileFiring {
     None,
     Around(Projectile, u8, u8),
     Forwards(Projectile, u8),
 }
 
-#[expect(dead_code)]
 #[derive(PartialEq, Eq, Hash)]
 pub enum EnemyTier {
     Light,
@@ -29,6 +132,7 @@ pub enum EnemyTier {
     Miniboss,
 }
 
+#[derive(Clone)]
 pub enum PhaseEndCondition {
     /// Phase lasts indefinitely
     None,
@@ -44,6 +148,7 @@ pub enum PhaseEndCondition {
     PlayerDistance(f32),
 }
 
+#[derive(Clone)]
 pub struct EnemyPhase {
     pub movement: EnemyMovement,
     pub firing: ProjectileFiring,
@@ -53,30 +158,66 @@ pub struct EnemyPhase {
     pub end: PhaseEndCondition,
 }
 
+/// Something that happens when an enemy dies
+#[derive(Clone)]
+pub enum DeathEffect {
+    /// Split in to this many enemies of a type
+    Split(Arc<EnemyType>, u8),
+    /// Fire this many copies of a projectile out in a ring
+    Explode(Projectile, u8),
+    /// Leave a projectile behind where the enemy died, such as a puddle that hurts the player
+    Hazard(Projectile),
+    /// Heal the other enemies within this many pixels by this much health
+    Heal(f32, f32),
+}
+
+#[derive(Clone)]
 pub struct EnemyType {
+    pub name: String,
     pub speed: f32,
     pub phases: Vec<EnemyPhase>,
     pub max_health: f32,
+    /// Fraction of damage of each type the enemy resists. Negative if it's weak to the type.
+    pub resistances: HashMap<DamageType, f32>,
+    /// Knockback is divided by weight, so heavier enemies are knocked back less
+    pub weight: f32,
+    /// Knockback speed, after weight, a hit has to be above to knock the enemy back and stun it
+    pub poise: f32,
+    /// What happens when the enemy dies, in order
+    pub on_death: Vec<DeathEffect>,
+    /// Sheet the phases' sprites are on
+    pub sheet: SheetId,
 }
 
 pub struct Enemy {
     pub ty: &'static EnemyType,
     pub id: usize,
     pub pos: Vec2,
+    /// Position before the last step, used to interpolate drawing between steps
+    pub prev_pos: Vec2,
     pub direction: Vec2,
     pub anim_frame: f32,
     /// Used only for [EnemyMovement::Wander]
     pub move_target: Option<Vec2>,
     pub health: f32,
     pub damage_frames: u8,
-    pub attack_counter: u8,
+    /// Frames left of the burst shown after a critical hit
+    pub crit_frames: u8,
+    pub attack_counter: f32,
     pub phase_index: usize,
     pub shield: f32,
     /// Used only when the current phase's end condition is [PhaseEndCondition::Frames]
     pub phase_frame_counter: u32,
+    pub status_effects: Vec<StatusEffect>,
+    /// Velocity the enemy is sliding at from being knocked back
+    pub knockback: Vec2,
+    /// Frames left until the enemy can move by itself and attack again after being knocked back
+    pub hit_stun: u8,
+    /// Damage taken if the enemy is slammed in to a wall before the knockback stops
+    pub impact_damage: Vec<(DamageType, f32)>,
 }
 impl Enemy {
-    pub fn new(ty: &'static EnemyType, pos: Vec2, id: usize) -> Self {
+    pub fn new(ty: &'static EnemyType, pos: Vec2, id: usize, rng: &mut Rng) -> Self {
         let firing_delay = match &ty.phases.first().unwrap().firing {
             ProjectileFiring::None => 0,
             ProjectileFiring::Around(_, delay, _) => *delay,
@@ -86,20 +227,141 @@ impl Enemy {
             ty,
             id,
             pos,
+            prev_pos: pos,
             direction: RIGHT,
             anim_frame: 0.0,
             move_target: None,
             health: ty.max_health,
             damage_frames: 0,
-            attack_counter: rand::gen_range(0, firing_delay),
+            crit_frames: 0,
+            attack_counter: rng.gen_range(0, firing_delay) as f32,
             phase_index: 0,
             shield: 0.0,
             phase_frame_counter: 0,
+            status_effects: Vec::new(),
+            knockback: Vec2::ZERO,
+            hit_stun: 0,
+            impact_damage: Vec::new(),
         }
     }
     pub fn get_phase(&self) -> &'static EnemyPhase {
         &self.ty.phases[self.phase_index]
     }
+    /// Damage taken from a hit of a type, after the enemy's resistance to it
+    pub fn resisted(&self, ty: DamageType, amt: f32) -> f32 {
+        match self.ty.resistances.get(&ty) {
+            Some(resistance) => amt * (1.0 - resistance),
+            None => amt,
+        }
+    }
+    /// Deal damage, which the shield takes first
+    pub fn take_damage(&mut self, amt: f32) {
+        if self.shield > 0.0 {
+            self.shield = (self.shield - amt).max(0.0);
+        } else {
+            self.health -= amt;
+        }
+    }
+    /// Apply a status effect, or add a stack to it and reset its duration if it's already applied
+    pub fn apply_status(&mut self, ty: StatusEffectType) {
+        if let Some(effect) = self.status_effects.iter_mut().find(|f| f.ty == ty) {
+            effect.stacks = (effect.stacks + 1).min(ty.max_stacks());
+            effect.frames = ty.duration();
+        } else {
+            self.status_effects.push(StatusEffect {
+                ty,
+                stacks: 1,
+                frames: ty.duration(),
+                age: 0,
+            });
+        }
+    }
+    /// Advance status effects by a frame, removing the ones that wore off.
+    /// Returns the damage they dealt this frame.
+    pub fn tick_status_effects(&mut self) -> Vec<(DamageType, f32)> {
+        let mut dealt = Vec::new();
+        for effect in self.status_effects.iter_mut() {
+            effect.frames -= 1;
+            effect.age += 1;
+            if effect.age % STATUS_TICK_FRAMES == 0
+                && let Some((ty, amt)) = effect.ty.tick_damage()
+            {
+                dealt.push((ty, amt * effect.stacks as f32));
+            }
+        }
+        for (ty, amt) in dealt.iter_mut() {
+            *amt = self.resisted(*ty, *amt);
+        }
+        self.status_effects.retain(|f| f.frames > 0);
+        for (_, amt) in dealt.iter() {
+            self.take_damage(*amt);
+        }
+        dealt
+    }
+    /// Knock the enemy back, unless the hit isn't strong enough to break its poise. It's
+    /// stunned for as long as the knockback would slide it, even if it hits a wall first.
+    /// Impact damage is taken if it hits a wall while sliding.
+    pub fn knock_back(&mut self, velocity: Vec2, impact_damage: Vec<(DamageType, f32)>) {
+        let velocity = velocity / self.ty.weight;
+        if velocity.length() <= self.ty.poise {
+            return;
+        }
+        let frames = (KNOCKBACK_STOP_SPEED / velocity.length()).ln() / KNOCKBACK_FRICTION.ln();
+        self.knockback = velocity;
+        self.hit_stun = self.hit_stun.max(frames.ceil().min(u8::MAX as f32) as u8);
+        self.impact_damage = impact_damage;
+    }
+    pub fn sliding(&self) -> bool {
+        self.knockback != Vec2::ZERO
+    }
+    /// Slide from knockback for a frame, slowing down until it stops
+    pub fn slide(&mut self) {
+        self.pos += self.knockback;
+        self.knockback *= KNOCKBACK_FRICTION;
+        if self.knockback.length() < KNOCKBACK_STOP_SPEED {
+            self.knockback = Vec2::ZERO;
+            self.impact_damage.clear();
+        }
+    }
+    /// Stop sliding after hitting a wall. If it was going fast enough, the impact damage is
+    /// taken, and returned.
+    pub fn slam(&mut self) -> Vec<(DamageType, f32)> {
+        let mut dealt = Vec::new();
+        if self.knockback.length() >= SLAM_SPEED {
+            for (ty, amt) in std::mem::take(&mut self.impact_damage) {
+                let amt = self.resisted(ty, amt);
+                self.take_damage(amt);
+                dealt.push((ty, amt));
+            }
+            if !dealt.is_empty() {
+                self.damage_frames = 5;
+            }
+        }
+        self.knockback = Vec2::ZERO;
+        self.impact_damage.clear();
+        dealt
+    }
+    pub fn staggered(&self) -> bool {
+        self.hit_stun > 0
+    }
+    pub fn move_multiplier(&self) -> f32 {
+        if self.staggered() {
+            return 0.0;
+        }
+        self.status_effects
+            .iter()
+            .map(|f| f.ty.move_multiplier())
+            .product()
+    }
+    pub fn attack_speed_multiplier(&self) -> f32 {
+        if self.staggered() {
+            return 0.0;
+        }
+        self.status_effects
+            .iter()
+            .map(|f| f.ty.attack_speed_multiplier())
+            .product()
+    }
     pub fn draw(&self, assets: &Assets) {
         let x = self.pos.x.floor();
         let y = self.pos.y.floor();
@@ -109,10 +371,14 @@ impl Enemy {
         };
         if self.damage_frames > 0 {
             gl_use_material(&COLOR_MOD_MATERIAL);
+            // critical hits flash red instead of white
+            if self.crit_frames > 0 {
+                COLOR_MOD_MATERIAL.set_uniform("color", COLORS[2]);
+            }
         }
         let phase = self.get_phase();
         let anim = (self.anim_frame / 3.0).floor() % phase.frames as f32;
-        assets.entities.draw_sprite(
+        assets.entity_sheet(self.ty.sheet).draw_sprite(
             x,
             y,
             phase.sprite_x + anim,
@@ -120,6 +386,18 @@ impl Enemy {
             Some(&draw_params),
         );
         gl_use_default_material();
+        COLOR_MOD_MATERIAL.set_uniform("color", COLORS[0]);
+        // draw critical hit burst, a ring growing out from the enemy
+        if self.crit_frames > 0 {
+            let progress = 1.0 - self.crit_frames as f32 / CRIT_FRAMES as f32;
+            draw_circle_lines(
+                self.pos.x,
+                self.pos.y,
+                6.0 + progress * 8.0,
+                1.0,
+                Color::from_hex(0xf6e36c),
+            );
+        }
         // draw shield
         if self.shield > 0.0 {
             draw_circle_lines(self.pos.x, self.pos.y, 8.0, 1.0, Color::from_hex(0x5ee9e9));
@@ -145,5 +423,20 @@ impl Enemy {
             3.0,
             Color::from_hex(0x2890dc),
         );
+
+        // draw status effect icons to the right of the health bar, a pip per stack
+        for (index, effect) in self.status_effects.iter().enumerate() {
+            let icon_x = start_x + width + 1.0 + index as f32 * 4.0;
+            draw_rectangle(icon_x, start_y, 3.0, 3.0, effect.ty.color());
+            for stack in 1..effect.stacks {
+                draw_rectangle(
+                    icon_x + 1.0,
+                    start_y + 2.0 + stack as f32 * 2.0,
+                    1.0,
+                    1.0,
+                    effect.ty.color(),
+                );
+            }
+        }
     }
 }
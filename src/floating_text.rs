@@ -0,0 +1,110 @@
+//! Damage numbers that float up from enemies when they're hit.
+
+use macroquad::prelude::*;
+
+use crate::{
+    assets::Assets,
+    projectiles::DamageType,
+    simulation::{Hit, HitKind},
+};
+
+/// Steps a number stays on screen after its last hit
+const LIFETIME: u16 = 40;
+/// Hits on an enemy within this many steps of a number's last hit are added to that number
+const MERGE_STEPS: u16 = 12;
+
+struct FloatingText {
+    enemy_id: usize,
+    ty: DamageType,
+    kind: HitKind,
+    pos: Vec2,
+    amount: f32,
+    /// Steps since the number was last hit
+    life: u16,
+}
+impl FloatingText {
+    fn text(&self) -> String {
+        let amount = if self.amount >= 1.0 {
+            format!("{}", self.amount.round())
+        } else {
+            format!("{:.1}", self.amount)
+        };
+        match self.kind {
+            HitKind::Normal | HitKind::Crit => format!("{}{amount}", self.ty.text_color()),
+            HitKind::Shield => format!("\x09({amount})"),
+            HitKind::Immune => "\x01immune".into(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct FloatingTextManager {
+    texts: Vec<FloatingText>,
+}
+impl FloatingTextManager {
+    /// Advance the numbers by a step of the simulation, and add the hits of that step.
+    /// Hits of the same type and kind on an enemy that was just hit are merged in to one number.
+    pub fn step(&mut self, hits: &[Hit]) {
+        for text in self.texts.iter_mut() {
+            text.life += 1;
+            text.pos.y -= 0.25;
+        }
+        self.texts.retain(|f| f.life < LIFETIME);
+
+        for hit in hits {
+            if let Some(text) = self.texts.iter_mut().find(|f| {
+                f.enemy_id == hit.enemy_id
+                    && f.ty == hit.ty
+                    && f.kind == hit.kind
+                    && f.life <= MERGE_STEPS
+            }) {
+                text.amount += hit.amount;
+                text.life = 0;
+                continue;
+            }
+            // start above the enemy's health bar, and above any numbers already there
+            let mut pos = hit.pos - Vec2::new(0.0, 16.0);
+            while self
+                .texts
+                .iter()
+                .any(|f| f.enemy_id == hit.enemy_id && (f.pos.y - pos.y).abs() < 5.0)
+            {
+                pos.y -= 6.0;
+            }
+            self.texts.push(FloatingText {
+                enemy_id: hit.enemy_id,
+                ty: hit.ty,
+                kind: hit.kind,
+                pos,
+                amount: hit.amount,
+                life: 0,
+            });
+        }
+    }
+    /// Amounts of the numbers shown above an enemy, oldest first
+    pub fn amounts(&self, enemy_id: usize) -> Vec<f32> {
+        self.texts
+            .iter()
+            .filter(|f| f.enemy_id == enemy_id)
+            .map(|f| f.amount)
+            .collect()
+    }
+    pub fn draw(&self, assets: &Assets) {
+        for text in self.texts.iter() {
+            let content = text.text();
+            // the text without its color codes, which don't take up space
+            let plain: String = content.chars().filter(|f| !f.is_ascii_control()).collect();
+            let width = plain.chars().count() as f32 * 4.0;
+            let x = (text.pos.x - width / 2.0).floor();
+            let y = text.pos.y.floor();
+            // critical hits get a red shadow, and immune hits a white one
+            let shadow = match text.kind {
+                HitKind::Crit => "\x02",
+                HitKind::Immune => "\x00",
+                HitKind::Normal | HitKind::Shield => "\x01",
+            };
+            assets.draw_text(&format!("{shadow}{plain}"), x + 1.0, y + 1.0);
+            assets.draw_text(&content, x, y);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Reloading assets while the game is running, so sprites, layouts and data files can be
+//! changed without restarting. Only in debug builds, which read assets from disk.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    assets::{ASSETS_DIR, Assets, read_asset},
+    data,
+    dungeon::{World, reload_layouts},
+    items::{Enchantment, Item},
+    mods,
+    simulation::{GameState, Simulation},
+    worlds::set_worlds,
+};
+
+/// Time between checks for changed files, as every file's modified time is read
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watches the assets directory for changed files
+pub struct AssetWatcher {
+    modified: HashMap<PathBuf, SystemTime>,
+    last_check: Instant,
+}
+impl AssetWatcher {
+    /// Start watching, with the files as they are now
+    pub fn new() -> Self {
+        let mut modified = HashMap::new();
+        find_modified_times(Path::new(ASSETS_DIR), &mut modified);
+        Self {
+            modified,
+            last_check: Instant::now(),
+        }
+    }
+    /// Returns the files changed since the last check, as paths in the assets directory
+    pub fn changed_files(&mut self) -> Vec<String> {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return Vec::new();
+        }
+        self.last_check = Instant::now();
+        let mut modified = HashMap::new();
+        find_modified_times(Path::new(ASSETS_DIR), &mut modified);
+        let mut changed: Vec<String> = modified
+            .iter()
+            .filter(|(path, time)| self.modified.get(*path) != Some(time))
+            .filter_map(|(path, _)| {
+                let path = path.strip_prefix(ASSETS_DIR).ok()?;
+                Some(path.to_string_lossy().replace('\\', "/"))
+            })
+            .collect();
+        changed.sort();
+        self.modified = modified;
+        changed
+    }
+}
+impl Default for AssetWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn find_modified_times(dir: &Path, out: &mut HashMap<PathBuf, SystemTime>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            find_modified_times(&path, out);
+        } else if let Ok(time) = entry.metadata().and_then(|f| f.modified()) {
+            out.insert(path, time);
+        }
+    }
+}
+
+/// Game data loaded again from the data files. Like worlds in [set_worlds], it's leaked,
+/// so runs that started with the old data can switch over to it.
+pub struct GameData {
+    pub items: &'static [Item],
+    pub enchantments: &'static [Enchantment],
+    pub worlds: &'static [World],
+}
+
+/// Reload changed files. Textures and layouts are replaced right away. If a data file changed,
+/// all of them are loaded again and new runs use the new worlds, and the data is returned so
+/// it can be given to a run with [apply_data]. Errors are printed, and keep the old assets.
+pub fn reload(assets: &Assets, files: &[String]) -> Option<GameData> {
+    let mut layouts_changed = false;
+    let mut data_changed = false;
+    for file in files {
+        if file.starts_with("layouts/") {
+            layouts_changed = true;
+        } else if file.starts_with("data/") {
+            data_changed = true;
+        } else {
+            match assets.reload_texture(file) {
+                Ok(true) => println!("reloaded {file}"),
+                Ok(false) => {}
+                Err(err) => eprintln!("failed to reload {file}: {err}"),
+            }
+        }
+    }
+    if layouts_changed {
+        match reload_layouts() {
+            Ok(()) => println!("reloaded layouts"),
+            Err(err) => eprintln!("failed to reload layouts: {err}"),
+        }
+    }
+    if !data_changed {
+        return None;
+    }
+    match load_data() {
+        Ok(data) => {
+            println!("reloaded data");
+            Some(data)
+        }
+        Err(err) => {
+            eprintln!("failed to reload data: {err}");
+            None
+        }
+    }
+}
+
+fn load_data() -> Result<GameData, String> {
+    let files = |name: &str, sheet: &str| -> Result<_, String> {
+        Ok(mods::data_files(
+            name,
+            sheet,
+            &read_asset(&format!("data/{name}"))?,
+        ))
+    };
+    let items = data::load_items(&files("items.ron", "items.ase")?)?;
+    let enchantments = data::load_enchantments(&files("enchantments.ron", "enchantments.ase")?)?;
+    let enemy_types = data::load_enemy_types(&files("enemies.ron", "entities.ase")?)?;
+    let worlds = data::load_worlds(&files("worlds.ron", "world.ase")?, &enemy_types)?;
+    Ok(GameData {
+        items: items.leak(),
+        enchantments: enchantments.leak(),
+        worlds: set_worlds(worlds),
+    })
+}
+
+/// Switch a run over to reloaded data. Items in the run get their new stats, keeping their
+/// enchantment, rolled damage and lives. Enemies get their new type, and the rooms to come
+/// are from the new worlds.
+pub fn apply_data(sim: &mut Simulation<'_>, data: &GameData) {
+    let old_items = sim.items;
+    for item in sim.player.items_mut() {
+        refresh_item(item, old_items, data);
+    }
+    for (_, item) in sim.dropped_items.iter_mut() {
+        refresh_item(item, old_items, data);
+    }
+    if let GameState::PostRound(_, Some(offers)) = &mut sim.state {
+        for item in offers.iter_mut() {
+            refresh_item(item, old_items, data);
+        }
+    }
+    sim.items = data.items;
+    sim.enchantments = data.enchantments;
+
+    let dungeon = &mut sim.dungeon_manager;
+    dungeon.worlds = data.worlds.iter().collect();
+    dungeon.world_index = dungeon.world_index.min(dungeon.worlds.len() - 1);
+    let world = dungeon.worlds[dungeon.world_index];
+    for enemy in sim.enemies.iter_mut() {
+        let new = [
+            &world.light,
+            &world.heavy,
+            &world.ranged,
+            &world.other,
+            &world.miniboss,
+        ]
+        .into_iter()
+        .flatten()
+        .find(|f| f.name == enemy.ty.name);
+        if let Some(new) = new {
+            enemy.ty = new;
+            enemy.phase_index %= new.phases.len();
+        }
+    }
+}
+
+/// Replace an item with its new definition, if it still has one
+fn refresh_item(item: &mut Item, old_items: &[Item], data: &GameData) {
+    let Some(new) = data.items.iter().find(|f| f.id == item.id) else {
+        return;
+    };
+    let mut refreshed = new.clone();
+    // damage is rolled when an item is found, as a multiple of the item's base damage
+    if let Some(old) = old_items.iter().find(|f| f.id == item.id) {
+        for (ty, amt) in refreshed.internal_stats.damage.iter_mut() {
+            if let Some(base) = old.internal_stats.damage.get(ty)
+                && let Some(rolled) = item.internal_stats.damage.get(ty)
+                && *base != 0.0
+            {
+                *amt *= rolled / base;
+            }
+        }
+    }
+    if item.internal_stats.max_lives > 0 {
+        refreshed.internal_stats.lives = item
+            .internal_stats
+            .lives
+            .min(refreshed.internal_stats.max_lives);
+    }
+    refreshed.enchantment = item.enchantment.take().map(|enchantment| {
+        data.enchantments
+            .iter()
+            .find(|f| f.id == enchantment.id)
+            .cloned()
+            .unwrap_or(enchantment)
+    });
+    *item = refreshed;
+}
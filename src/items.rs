@@ -1,30 +1,81 @@
 mod types;
-use std::{borrow::Cow, mem::discriminant};
+use std::{borrow::Cow, collections::HashMap, mem::discriminant};
 
 pub use types::*;
 
-use crate::{assets::Assets, player::Stats, projectiles::Projectile};
+use crate::{
+    assets::{Assets, SheetId},
+    player::Stats,
+    projectiles::{DamageType, Projectile},
+};
 
 #[derive(Clone)]
 pub struct Weapon {
     /// Projectile fired
     pub projectile: Projectile,
+    /// How the attack charges up while held. None if it fires as soon as it can
+    pub charge: Option<ChargeProfile>,
+}
+
+/// How much stronger a weapon's projectile gets while the attack is held before releasing it.
+/// Each multiplier goes up from 1 with no charge to its value at full charge.
+#[derive(Clone)]
+pub struct ChargeProfile {
+    /// Frames it takes to fully charge
+    pub frames: u16,
+    pub damage: f32,
+    pub speed: f32,
+    /// Multiplier of the projectile's radius
+    pub size: f32,
+    /// Multiplier of the player's move speed while charging
+    pub move_speed: f32,
+}
+impl ChargeProfile {
+    /// Multiplier at a fraction of full charge, going from 1 up to the full charge value
+    pub fn scale(full: f32, charge: f32) -> f32 {
+        1.0 + (full - 1.0) * charge
+    }
+}
+
+/// Item used with its own button, which then can't be used again until its cooldown is over
+#[derive(Clone)]
+pub struct ActiveItem {
+    pub ability: Ability,
+    /// Frames before the item can be used again, before the player's cooldown modifier
+    pub cooldown: f32,
+}
+
+#[derive(Clone)]
+pub enum Ability {
+    /// Throw a projectile towards the aim, dealing this damage instead of the held weapon's
+    Throw(Projectile, HashMap<DamageType, f32>),
+    /// Teleport this many pixels in the direction the player is moving, or towards the aim
+    Blink(f32),
+    /// Block all damage for this many frames
+    Shield(u16),
+    /// Spawn this many copies of a projectile around the player, dealing this damage
+    Summon(Projectile, HashMap<DamageType, f32>, u8),
 }
 
 #[derive(Clone)]
 pub struct Enchantment {
-    name: &'static str,
-    stats: Stats,
+    /// Stable identifier used by saves
+    pub id: u16,
+    pub name: String,
+    pub stats: Stats,
+    pub sheet: SheetId,
     pub sprite_x: f32,
     pub sprite_y: f32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub enum ItemType {
+    #[default]
     Helmet,
     Chestplate,
     Held(Box<Weapon>),
     Talisman,
+    Active(Box<ActiveItem>),
 }
 impl ItemType {
     pub fn draw_icon(&self, x: f32, y: f32, assets: &Assets) {
@@ -33,6 +84,7 @@ impl ItemType {
             ItemType::Helmet => 1.0,
             ItemType::Held(_) => 2.0,
             ItemType::Talisman => 3.0,
+            ItemType::Active(_) => 4.0,
         };
         assets.items.draw_sprite(x, y, 0.0, ty, None);
     }
@@ -42,16 +94,14 @@ impl PartialEq for ItemType {
         discriminant(self) == discriminant(other)
     }
 }
-impl Default for ItemType {
-    fn default() -> Self {
-        Self::Helmet
-    }
-}
 
 #[derive(Clone, Default)]
 pub struct Item {
-    pub name: &'static str,
+    /// Stable identifier used by saves
+    pub id: u16,
+    pub name: String,
     pub ty: ItemType,
+    pub sheet: SheetId,
     pub sprite_x: f32,
     pub sprite_y: f32,
     pub internal_stats: Stats,
@@ -62,7 +112,7 @@ impl Item {
         if let Some(e) = &self.enchantment {
             Cow::Owned(format!("{} of {}", self.name, e.name))
         } else {
-            Cow::Borrowed(self.name)
+            Cow::Borrowed(&self.name)
         }
     }
     pub fn stats(&self) -> Stats {
@@ -73,3 +123,10 @@ impl Item {
         stats
     }
 }
+
+pub fn get_item_by_name<'a>(items: &'a [Item], name: &str) -> Result<&'a Item, String> {
+    items
+        .iter()
+        .find(|f| f.name == name)
+        .ok_or(format!("no item named '{name}'"))
+}
@@ -0,0 +1,22 @@
+//! Ramble's game logic, shared by the game and the `ramble-sim` balance simulator.
+
+pub mod assets;
+pub mod data;
+pub mod dungeon;
+pub mod enemy;
+pub mod floating_text;
+#[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+pub mod hot_reload;
+pub mod items;
+pub mod mods;
+pub mod particles;
+pub mod player;
+pub mod projectiles;
+pub mod replay;
+pub mod rng;
+pub mod save;
+pub mod simulation;
+pub mod spatial;
+pub mod ui;
+pub mod utils;
+pub mod worlds;
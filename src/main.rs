@@ -1,595 +1,226 @@
-use std::f32::consts::PI;
-
-use hashmap_macro::hashmap;
 use macroquad::{miniquad::window::screen_size, prelude::*};
 
-mod assets;
-mod dungeon;
-mod enemy;
-mod items;
-mod particles;
-mod player;
-mod projectiles;
-mod ui;
-mod utils;
-mod worlds;
-use assets::*;
-use dungeon::*;
-use enemy::*;
-use items::*;
-use player::*;
-use projectiles::*;
-use ui::UiManager;
-use utils::*;
-use worlds::*;
-
-enum GameState {
-    RoundActive,
-    PostRound(u32, Option<[Item; 3]>),
-    PreRound(u32),
-    /// Bool is if game over is because of win. False if loss
-    GameOver(u32, bool),
-}
-impl GameState {
-    fn should_draw(&self) -> bool {
-        match self {
-            GameState::GameOver(_, _) | GameState::RoundActive | GameState::PostRound(_, _) => true,
-            GameState::PreRound(frame) => *frame >= 40 / 2,
-        }
-    }
-    fn door_frame(&self) -> f32 {
-        match self {
-            GameState::GameOver(_, _) => 0.0,
-            GameState::RoundActive => 0.0,
-            GameState::PostRound(frame, _) => (*frame as f32 / 10.0 * 4.0).floor().min(3.0) * 2.0,
-            GameState::PreRound(frame) => {
-                if *frame >= PREROUND_TRANSITION_TIME / 2 {
-                    0.0
-                } else {
-                    6.0
-                }
-            }
-        }
-    }
-}
+#[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+use ramble::hot_reload::{self, AssetWatcher};
+use ramble::{
+    assets::*,
+    floating_text::FloatingTextManager,
+    mods,
+    player::*,
+    replay::*,
+    save,
+    simulation::*,
+    ui::{self, UiManager},
+    utils::*,
+    worlds,
+};
+
 struct Ramble<'a> {
     assets: &'a Assets,
-    player: Player,
-    state: GameState,
-    enemies: Vec<Enemy>,
-    dropped_items: Vec<(Vec2, Item)>,
-    enemy_id: usize,
-    projectiles: Vec<Projectile>,
+    sim: Simulation<'a>,
     ui_camera: Camera2D,
     prev_screen_size: (f32, f32),
-    dungeon_manager: DungeonManager,
     ui_manager: UiManager,
+    floating_text: FloatingTextManager,
+    /// Whether interact was pressed since the last update
+    interact_pressed: bool,
+    /// Inputs of the run so far
+    replay: Replay,
+    /// Set if watching a replay instead of playing
+    playback: Option<Playback>,
+    settings: Settings,
+    /// The last save written, so it is only written again when something changes
+    last_save: Option<String>,
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    watcher: AssetWatcher,
 }
 impl<'a> Ramble<'a> {
-    fn new(assets: &'a Assets, player: Player) -> Self {
+    fn new(assets: &'a Assets, sim: Simulation<'a>, replay: Replay, settings: Settings) -> Self {
         Ramble {
-            state: GameState::PostRound(100, None),
             assets,
-            player,
-            enemies: Vec::new(),
-            dropped_items: Vec::new(),
-            enemy_id: 0,
-            projectiles: Vec::new(),
+            sim,
             prev_screen_size: (0.0, 0.0),
             ui_camera: create_camera(SCREEN_WIDTH, SCREEN_HEIGHT),
-            dungeon_manager: DungeonManager::new(vec![&FOREST, &CRYPT]),
             ui_manager: UiManager::default(),
+            floating_text: FloatingTextManager::default(),
+            interact_pressed: false,
+            replay,
+            playback: None,
+            settings,
+            last_save: None,
+            #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+            watcher: AssetWatcher::new(),
         }
     }
-    fn get_item_reward(&self) -> Item {
-        let mut item = select_random(&self.assets.all_items).clone();
-        let room_modifier_index = self.dungeon_manager.total_room_index / 2;
-        for (_, v) in item.internal_stats.damage.iter_mut() {
-            *v *= rand::gen_range(1.0, 1.25 + room_modifier_index as f32);
-        }
-
-        if let ItemType::Held(_) = item.ty
-            && rand::gen_range(0, 100) <= ENCHANT_CHANCE
+    fn new_playback(
+        assets: &'a Assets,
+        sim: Simulation<'a>,
+        replay: Replay,
+        settings: Settings,
+    ) -> Self {
+        let new_replay = Replay::new(replay.seed, replay.class_index);
+        let mut ramble = Self::new(assets, sim, new_replay, settings);
+        ramble.ui_manager.read_only = true;
+        ramble.playback = Some(Playback::new(replay));
+        ramble
+    }
+    /// Save the run if it is between rooms and has changed since it was last saved. Only
+    /// called at checkpoints: clearing a room, interacting and changing the inventory, so the
+    /// active item cooling down doesn't write the save every step
+    fn autosave(&mut self) {
+        if self.playback.is_none()
+            && let Some(save) = self.sim.to_save()
+            && self.last_save.as_ref() != Some(&save)
         {
-            let enchant = select_random(&self.assets.all_enchantments).clone();
-            item.enchantment = Some(enchant);
+            save::write_save(&save);
+            self.last_save = Some(save);
         }
-        item
     }
-    fn give_curse(&mut self, curse: ChaosCurse) {
-        let mut lost_items = Vec::new();
-        match &curse {
-            ChaosCurse::RefillHealth => {
-                self.player.regen();
-            }
-            ChaosCurse::RepairArmor => {
-                self.player.repair_armor();
-            }
-            ChaosCurse::DoubledUnholyDmg => {
-                self.player.internal_stats.merge(&Stats {
-                    damage_modifiers: hashmap!(DamageType::Unholy=>1.0),
-                    ..Default::default()
-                });
-            }
-            ChaosCurse::HalvedHolyDmg => {
-                self.player.internal_stats.merge(&Stats {
-                    damage_modifiers: hashmap!(DamageType::Holy=>-0.5),
-                    ..Default::default()
-                });
-            }
-            ChaosCurse::Gift => {
-                let pos = Vec2::new(SCREEN_WIDTH / 2.0, 64.0);
-                self.dropped_items.push((pos, gift()));
-            }
-            _ => {}
-        }
-        self.player.curses.push(curse);
-        let inventory_curse_count = self
-            .player
-            .curses
-            .iter()
-            .filter(|f| matches!(*f, ChaosCurse::LessInventory))
-            .count();
-
-        for (index, item) in self.player.inventory.iter_mut().enumerate() {
-            if index < inventory_curse_count * 2
-                && let Some(item) = item.take()
-            {
-                lost_items.push(item);
-            }
-        }
-        for item in lost_items.into_iter() {
-            if self.player.inv_slot_free(&item.ty) {
-                self.player.give_item(item);
-            } else {
-                let pos = self.player.pos + Vec2::from_angle(rand::gen_range(0.0, PI * 2.0)) * 5.0;
-                self.dropped_items.push((pos, item));
-            }
+    /// Reload assets changed on disk, and switch the run over to changed data
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    fn hot_reload(&mut self) {
+        let files = self.watcher.changed_files();
+        if let Some(data) = hot_reload::reload(self.assets, &files) {
+            hot_reload::apply_data(&mut self.sim, &data);
         }
     }
-    fn spawn_enemies(&mut self, buffer: &mut Vec<Enemy>) {
-        let shield_count = self
-            .player
-            .curses
-            .iter()
-            .filter(|f| matches!(*f, ChaosCurse::EnemyShields))
-            .count();
-        for mut enemy in buffer.drain(..) {
-            enemy.id = self.enemy_id;
-            enemy.pos.y += 32.0;
-            enemy.shield = shield_count as f32 * 5.0;
-            self.enemy_id += 1;
-            self.enemies.push(enemy);
+    fn read_input(&mut self, mouse_x: f32, mouse_y: f32) -> InputFrame {
+        InputFrame {
+            move_vector: get_movement_vector(),
+            aim: Vec2::new(mouse_x, mouse_y),
+            attack: is_mouse_button_down(MouseButton::Left),
+            roll: is_key_down(KeyCode::Space),
+            ability: is_key_down(KeyCode::Q) || is_mouse_button_down(MouseButton::Right),
+            interact: std::mem::take(&mut self.interact_pressed),
         }
     }
-    fn update(&mut self, mouse_x: f32, mouse_y: f32) {
-        let (move_vector, speed) = if self.player.roll.0 == 0 {
-            (get_movement_vector(), self.player.stats().move_speed)
-        } else {
-            (self.player.roll.1, 4.0)
-        };
-        let start_y = 28.0;
-
-        let top_left_corner = Vec2::new(4.0, start_y);
-        let bottom_right_corner = Vec2::new(SCREEN_WIDTH - 4.0, SCREEN_HEIGHT - 8.0);
-
-        self.player.pos =
-            (self.player.pos + move_vector * speed).clamp(top_left_corner, bottom_right_corner);
-        let door_start_x = TILES_WIDTH / 2 - 1;
-
-        // go to next room
-        if let GameState::PostRound(_, _) = self.state
-            && self.player.pos.y == 28.0
-            && (door_start_x as f32 * 16.0 + 4.0..door_start_x as f32 * 16.0 + 28.0)
-                .contains(&self.player.pos.x)
-            && is_key_down(KeyCode::W)
+    fn draw_ui(&mut self, mouse_x: f32, mouse_y: f32, ui_width: f32) {
+        if let Some(action) =
+            self.ui_manager
+                .update(self.assets, &self.sim.player, mouse_x, mouse_y, ui_width)
+            && self.playback.is_none()
         {
-            self.player.pos = Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT - 16.0);
-            self.dropped_items.clear();
-            self.enemies.clear();
-            self.projectiles.clear();
-            let e = self.dungeon_manager.spawn_room();
-            if let Some(mut e) = e {
-                self.spawn_enemies(&mut e);
-            } else {
-                self.state = GameState::GameOver(0, true);
-                return;
-            }
-            let mut extra_enemies = Vec::new();
-            for curse in self.player.curses.iter() {
-                match *curse {
-                    ChaosCurse::AcidPuddles => {
-                        for _ in 0..2 {
-                            let mut new = acid_puddle();
-                            new.pos = Vec2::new(
-                                rand::gen_range(top_left_corner.x, bottom_right_corner.x),
-                                rand::gen_range(top_left_corner.y, bottom_right_corner.y - 48.0),
-                            );
-                            new.origin = new.pos;
-                            self.projectiles.push(new);
-                        }
-                    }
-                    ChaosCurse::BonusEnemies => {
-                        let (types, amt) = if rand::gen_range(0, 100) < 50 {
-                            (
-                                &self.dungeon_manager.worlds[self.dungeon_manager.world_index]
-                                    .other,
-                                2,
-                            )
-                        } else {
-                            (
-                                &self.dungeon_manager.worlds[self.dungeon_manager.world_index]
-                                    .miniboss,
-                                1,
-                            )
-                        };
-                        for _ in 0..amt {
-                            let enemy = Enemy::new(
-                                select_random(&types),
-                                Vec2::new(
-                                    rand::gen_range(top_left_corner.x, bottom_right_corner.x),
-                                    rand::gen_range(
-                                        top_left_corner.y,
-                                        bottom_right_corner.y - 48.0,
-                                    ),
-                                ),
-                                0,
-                            );
-                            extra_enemies.push(enemy);
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            self.spawn_enemies(&mut extra_enemies);
-            self.state = GameState::PreRound(0);
-        }
-
-        if move_vector != Vec2::ZERO {
-            self.player.moving = true;
-            self.player.anim_frame += speed;
-        } else {
-            self.player.moving = false;
+            let action = self.replay.record_inventory(&action);
+            self.sim.apply_inventory_action(&action);
+            self.autosave();
         }
-
-        // tick counters
-        if self.player.attack_counter > 0.0 {
-            self.player.attack_counter -= 1.0
+        if let Some(playback) = &self.playback {
+            let mut text = format!("replay {}x", playback.speed);
+            if playback.is_done() {
+                text.push_str(" - end");
+            } else if playback.paused {
+                text.push_str(" - paused");
+            }
+            self.assets.draw_text(&text, 2.0, 2.0);
+            self.assets.draw_text(
+                "space: pause\nright: step\nup/down: speed\nescape: exit",
+                2.0,
+                9.0,
+            );
         }
-        if self.player.roll_counter > 0.0 {
-            self.player.roll_counter -= 1.0
+    }
+    /// Handle replay controls, and play the steps of the ticks due this frame.
+    /// Returns whether the replay is playing, as opposed to paused or finished.
+    fn update_playback(&mut self, ticks: u32) -> bool {
+        let Some(playback) = &mut self.playback else {
+            return false;
+        };
+        if is_key_pressed(KeyCode::Space) {
+            playback.paused = !playback.paused;
         }
-        self.player.roll.0 = self.player.roll.0.saturating_sub(1);
-        self.player.invuln_frames = self.player.invuln_frames.saturating_sub(1);
-
-        // player combat roll
-        if is_key_down(KeyCode::Space) && self.player.roll_counter <= 0.0 && self.player.moving {
-            self.player.roll_counter = self.player.stats().roll_delay;
-            self.player.roll = (12, move_vector)
+        if is_key_pressed(KeyCode::Up) {
+            playback.speed = (playback.speed * 2).min(16);
         }
-
-        // player attack
-        if is_mouse_button_down(MouseButton::Left)
-            && self.player.attack_counter <= 0.0
-            && let Some(held) = &self.player.hand
-            && let ItemType::Held(held) = &held.ty
-        {
-            let mut projectile = held.projectile.clone();
-            let delta = (Vec2::new(mouse_x, mouse_y) - self.player.pos).normalize();
-            projectile.pos = self.player.pos + delta * 10.0;
-            projectile.direction = delta;
-            projectile.player_owned = true;
-            projectile.stats = Some(self.player.stats());
-            // make projectile travel faster if self.player is moving in same direction they're shooting,
-            // relative to the projectiles drag. more drag => more affected by players move direction
-            projectile.speed +=
-                (25.0 * projectile.drag) * (projectile.direction.dot(move_vector).max(0.0));
-            projectile.origin = projectile.pos;
-            self.projectiles.push(projectile);
-            self.player.attack_counter = self.player.stats().attack_delay;
+        if is_key_pressed(KeyCode::Down) {
+            playback.speed = (playback.speed / 2).max(1);
         }
-        let mut new_projectiles = Vec::new();
-
-        self.projectiles.retain_mut(|projectile| {
-            projectile.speed = projectile.speed.lerp(0.0, projectile.drag);
-            projectile.life += 1;
-            // split the projectiles movement in to chunks so that projectiles moving fast wont pass through enemies
-            let speed_chunks = if projectile.speed <= 6.0 {
-                vec![projectile.speed]
-            } else {
-                let amt = (projectile.speed / 6.0).floor() + 1.0;
-                vec![projectile.speed / amt; amt as usize]
-            };
-            for speed in speed_chunks.into_iter() {
-                projectile.pos += projectile.direction * speed;
-                if projectile.boomerang {
-                    projectile.speed -= 0.1;
-                    if projectile.speed < 0.0 && projectile.pos.distance(projectile.origin) <= 4.0 {
-                        return false;
-                    }
-                }
-
-                // check for collisions
-                if projectile.player_owned
-                    && let Some(stats) = &projectile.stats
-                {
-                    for enemy in self.enemies.iter_mut() {
-                        if !projectile.hit_enemies.contains(&enemy.id)
-                            && (enemy.pos - projectile.pos).length() <= projectile.radius * 1.5
-                        {
-                            // todo: make projectiles moving faster than 8.0 pixels/frame have their hit scan split in to multiple steps
-                            for (k, mut amt) in stats.damage.clone() {
-                                if let Some(modifier) = self.player.stats().damage_modifiers.get(&k)
-                                {
-                                    amt *= 1.0 + modifier;
-                                }
-                                if enemy.shield > 0.0 {
-                                    enemy.shield = (enemy.shield - amt).max(0.0);
-                                } else {
-                                    enemy.health -= amt;
-                                }
-                            }
-                            enemy.damage_frames = 5;
-                            projectile.hit_enemies.push(enemy.id);
-                            // dont allow projectile children to trigger on hit effects on the same enemy
-                            if !projectile.parent_hit_enemies.contains(&enemy.id) {
-                                new_projectiles.append(&mut projectile.on_hit());
-                            }
-                        }
-                    }
-                } else if self.player.can_take_damage() {
-                    let distance = (self.player.pos - projectile.pos).length();
-                    if distance <= projectile.radius {
-                        if self.player.damage() {
-                            self.state = GameState::GameOver(0, false);
-                        }
-                    }
-                }
-            }
-            let old = projectile.pos;
-            projectile.pos = projectile.pos.clamp(top_left_corner, bottom_right_corner);
-            if projectile.pos != old {
-                // projectile collided with wall
-                new_projectiles.append(&mut projectile.on_hit());
-                return false;
-            }
-
-            projectile.life < projectile.lifetime
-        });
-        self.projectiles.append(&mut new_projectiles);
-        let enemy_positions: Vec<Vec2> = self.enemies.iter().map(|f| f.pos).collect();
-
-        self.enemies.retain_mut(|enemy| {
-            let player_delta = self.player.pos - enemy.pos;
-            enemy.damage_frames = enemy.damage_frames.saturating_sub(1);
-            let mut move_direction = Vec2::ZERO;
-            let phase = enemy.get_phase();
-            // move
-            match &phase.movement {
-                EnemyMovement::Chase => {
-                    enemy.direction = player_delta.normalize();
-                    move_direction = enemy.direction;
-                }
-                EnemyMovement::Wander(face_player) => {
-                    let mut new_target = true;
-                    if let Some(move_target) = enemy.move_target {
-                        new_target = false;
-                        let delta = move_target - enemy.pos;
-                        move_direction = delta.normalize();
-                        if *face_player {
-                            enemy.direction = player_delta.normalize();
-                        } else {
-                            enemy.direction = move_direction;
-                        }
-                        if delta.length() <= 4.0 {
-                            new_target = true;
-                        }
-                    }
-                    if new_target {
-                        // set new move target if either no previous move target was set,
-                        // or distance was less than 4.0
-                        enemy.move_target = Some(Vec2::new(
-                            rand::gen_range(top_left_corner.x, bottom_right_corner.x),
-                            rand::gen_range(top_left_corner.y, bottom_right_corner.y),
-                        ));
-                    }
-                }
-                EnemyMovement::Still => {}
-                EnemyMovement::Fowards => {
-                    move_direction = enemy.direction;
-                }
-            }
-            for pos in enemy_positions.iter() {
-                if pos != &enemy.pos {
-                    let delta = enemy.pos - *pos;
-                    if delta.length() <= 7.0 {
-                        move_direction = delta.normalize();
-                    }
-                }
-            }
-            enemy.pos += move_direction * enemy.ty.speed;
-            enemy.anim_frame += enemy.ty.speed;
-            // shoot
-            if enemy.attack_counter == 0 {
-                match &phase.firing {
-                    ProjectileFiring::Forwards(projectile, delay) => {
-                        enemy.attack_counter = *delay;
-                        let mut projectile = projectile.clone();
-                        projectile.pos = enemy.pos;
-                        projectile.direction = enemy.direction;
-                        projectile.player_owned = false;
-                        projectile.origin = projectile.pos;
-                        self.projectiles.push(projectile);
-                    }
-                    ProjectileFiring::Around(projectile, delay, amt) => {
-                        enemy.attack_counter = *delay;
-                        for i in 0..*amt {
-                            let angle =
-                                enemy.direction.to_angle() + i as f32 * (PI * 2.0) / *amt as f32;
-                            let direction = Vec2::from_angle(angle);
-                            let mut projectile = projectile.clone();
-                            projectile.pos = enemy.pos;
-                            projectile.direction = direction;
-                            projectile.player_owned = false;
-                            projectile.origin = projectile.pos;
-                            self.projectiles.push(projectile);
-                        }
+        let steps = if playback.paused {
+            is_key_pressed(KeyCode::Right) as u32
+        } else {
+            playback.speed * ticks
+        };
+        for _ in 0..steps {
+            for event in playback.next_step() {
+                match event {
+                    ReplayEvent::Step(input) => {
+                        self.sim.step(input);
+                        self.floating_text.step(&self.sim.hits);
                     }
-                    ProjectileFiring::None => {}
+                    ReplayEvent::Inventory(action) => self.sim.apply_inventory_action(action),
                 }
-            } else {
-                enemy.attack_counter -= 1
             }
-            let mut collision = false;
-
-            let old = enemy.pos;
-            enemy.pos = enemy.pos.clamp(top_left_corner, bottom_right_corner);
-            if enemy.pos != old {
-                collision = true;
+            if self.sim.finished().is_some() {
+                break;
             }
-
-            // dmg player on contact
-            if player_delta.length() <= 4.0 && self.player.can_take_damage() {
-                if self.player.damage() {
-                    self.state = GameState::GameOver(0, false);
-                }
-                collision = true;
-            }
-
-            // check if phase should change
-            let phase_end = match &phase.end {
-                PhaseEndCondition::None => false,
-                PhaseEndCondition::SingleFrame => true,
-                PhaseEndCondition::Collision => collision,
-                PhaseEndCondition::PlayerDistance(dist) => player_delta.length() <= *dist,
-                PhaseEndCondition::Frames(target) => {
-                    if enemy.phase_frame_counter >= *target {
-                        enemy.phase_frame_counter = 0;
-                        true
-                    } else {
-                        enemy.phase_frame_counter += 1;
-                        false
-                    }
-                }
-                PhaseEndCondition::HealthUnder(percent) => {
-                    enemy.health / enemy.ty.max_health <= *percent
-                }
-            };
-            if phase_end {
-                enemy.phase_index += 1;
-                if enemy.phase_index >= enemy.ty.phases.len() {
-                    enemy.phase_index = 0;
-                }
-            }
-
-            enemy.health > 0.0
-        });
-
-        if let GameState::RoundActive = self.state
-            && self.enemies.is_empty()
-        {
-            self.projectiles.retain(|f| f.player_owned);
-            self.state =
-                GameState::PostRound(0, Some(std::array::from_fn(|_| self.get_item_reward())))
-        }
-    }
-    fn draw_ui(&mut self, mouse_x: f32, mouse_y: f32, ui_width: f32) {
-        if let Some(dropped) =
-            self.ui_manager
-                .update(self.assets, &mut self.player, mouse_x, mouse_y, ui_width)
-        {
-            let mut pos = self.player.pos;
-            pos += (Vec2::new(mouse_x, mouse_y) - self.player.pos).normalize() * 3.0;
-            self.dropped_items.push((pos, dropped));
         }
+        !playback.paused && !playback.is_done()
     }
-    fn draw(&mut self, mouse_x: f32, mouse_y: f32) {
+    /// Draw the world. Moving things are drawn `alpha` of the way from where they were
+    /// before the last step to where they are now.
+    fn draw(&mut self, mouse_x: f32, mouse_y: f32, alpha: f32) {
         // this shouldnt be necessary but for some reason it is
         COLOR_MOD_MATERIAL.set_uniform("color", COLORS[0]);
-        for enemy in self.enemies.iter() {
+        for enemy in self.sim.enemies.iter_mut() {
+            let pos = enemy.pos;
+            enemy.pos = enemy.prev_pos.lerp(pos, alpha);
             enemy.draw(self.assets);
+            enemy.pos = pos;
         }
 
-        let mut item_under_player: Option<(usize, f32)> = None;
-
-        for (index, (pos, item)) in self.dropped_items.iter().enumerate() {
+        for (pos, item) in self.sim.dropped_items.iter() {
             ui::draw_slot(Some(item), pos.x - 6.0, pos.y - 6.0, 0.0, 0.0, self.assets);
-
-            let dist = pos.distance(self.player.pos);
-            if dist <= 7.0 && item_under_player.is_none_or(|f| f.1 > dist) {
-                item_under_player = Some((index, dist));
-            }
         }
-        if let Some(item_under_player) = item_under_player {
-            let r = &self.dropped_items[item_under_player.0].1;
-            if self.player.inv_slot_free(&r.ty) {
+        if let Some(index) = self.sim.item_under_player() {
+            let r = &self.sim.dropped_items[index].1;
+            if self.sim.player.inv_slot_free(&r.ty) {
                 ui::draw_item_tooltip(r, self.assets);
                 ui::draw_tooltip("e: pick up", self.assets);
-                if is_key_pressed(KeyCode::E) {
-                    let item = self.dropped_items.remove(item_under_player.0).1;
-                    if item.name == "gift" {
-                        self.player.give_item(self.get_item_reward());
-                    } else {
-                        self.player.give_item(item);
-                    }
-                }
             } else {
                 ui::draw_tooltip("inventory full", self.assets);
             }
         }
 
-        self.player.draw(self.assets, mouse_x, mouse_y);
+        let player = &mut self.sim.player;
+        let pos = player.pos;
+        player.pos = player.prev_pos.lerp(pos, alpha);
+        player.draw(self.assets, mouse_x, mouse_y);
+        player.pos = pos;
 
-        for projectile in self.projectiles.iter() {
+        for projectile in self.sim.projectiles.iter_mut() {
+            let pos = projectile.pos;
+            projectile.pos = projectile.prev_pos.unwrap_or(pos).lerp(pos, alpha);
             projectile.draw(self.assets);
+            projectile.pos = pos;
+        }
+
+        if self.settings.damage_numbers {
+            self.floating_text.draw(self.assets);
         }
     }
-    fn handle_item_shop(&mut self) {
-        if let GameState::PostRound(frame, items) = &mut self.state {
+    fn draw_item_shop(&self) {
+        if let GameState::PostRound(frame, items) = &self.sim.state {
             if *frame <= 40 {
                 let y = 88.0;
                 let anim = (*frame as f32 / 30.0).min(1.0);
                 let x = SCREEN_WIDTH / 2.0;
                 draw_ellipse(x, y + 20.0, anim * 48.0, anim * 16.0, 0.0, BLACK);
                 if *frame > 30 {
-                    let y = y + 14.0 - (*frame - 30) as f32 / 10.0 * 14.0;
+                    let y = shop_y(*frame);
                     draw_texture(&self.assets.shop, x - 3.0 * 16.0, y, WHITE);
-                    for index in 0..3 {
+                    let Some(items) = items else {
+                        return;
+                    };
+                    for (index, item) in items.iter().enumerate() {
                         let x = x - 32.0 + 16.0 * index as f32;
-
-                        if let Some(item) = items.as_ref().map(|f| &f[index]) {
-                            ui::draw_slot(
-                                Some(item),
-                                x + 2.0 - 8.0,
-                                y + 1.0,
-                                0.0,
-                                0.0,
-                                self.assets,
-                            );
-
-                            // check for distance with a bias against X coordinate
-                            if (self.player.pos.x - x).powi(2) * 3.0
-                                + (self.player.pos.y - y).powi(2)
-                                <= 18.0_f32.powi(2)
-                            {
-                                if self.player.inv_slot_free(&item.ty) {
-                                    ui::draw_tooltip("e: deal with the chaos demon", self.assets);
-                                    ui::draw_item_tooltip(item, self.assets);
-                                    if is_key_pressed(KeyCode::E) {
-                                        let o = items.take().unwrap();
-                                        self.player
-                                            .give_item(o.into_iter().skip(index).next().unwrap());
-                                        let all =
-                                            enum_iterator::all::<ChaosCurse>().collect::<Vec<_>>();
-                                        let item = *select_random(&all);
-                                        *frame = 41;
-                                        self.give_curse(item);
-                                        return;
-                                    }
-                                } else {
-                                    ui::draw_tooltip("inventory full", self.assets);
-                                }
-                            }
+                        ui::draw_slot(Some(item), x + 2.0 - 8.0, y + 1.0, 0.0, 0.0, self.assets);
+                    }
+                    if let Some(index) = self.sim.shop_item_in_range() {
+                        let item = &items[index];
+                        if self.sim.player.inv_slot_free(&item.ty) {
+                            ui::draw_tooltip("e: deal with the chaos demon", self.assets);
+                            ui::draw_item_tooltip(item, self.assets);
+                        } else {
+                            ui::draw_tooltip("inventory full", self.assets);
                         }
                     }
                 }
@@ -602,25 +233,16 @@ impl<'a> Ramble<'a> {
             }
         }
     }
-    async fn run(&mut self) -> bool {
+    /// Play the run until it is over. Returns whether it was won,
+    /// or None if it was left before it ended.
+    async fn run(&mut self) -> Option<bool> {
         let mut world_camera = create_camera(SCREEN_WIDTH, SCREEN_HEIGHT);
         let mut last = get_time();
-
-        #[cfg(debug_assertions)]
-        {
-            for (index, item) in self.assets.all_items.clone().into_iter().enumerate() {
-                let w = ((SCREEN_WIDTH - 40.0) / 14.0).floor();
-                self.dropped_items.push((
-                    Vec2::new(
-                        20.0 + ((index as f32) % w).floor() * 14.0,
-                        ((index as f32) / w).floor() * 14.0 + 48.0,
-                    ),
-                    item,
-                ));
-            }
-        }
+        let mut accumulator = 0.0;
 
         loop {
+            #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+            self.hot_reload();
             let (screen_width, screen_height) = screen_size();
             let scale_factor = (screen_width / SCREEN_WIDTH).min(screen_height / SCREEN_HEIGHT);
             let horizontal_padding = (screen_width - SCREEN_WIDTH * scale_factor) / 2.0;
@@ -640,12 +262,12 @@ impl<'a> Ramble<'a> {
                 self.ui_camera = create_camera(ui_width, ui_height);
             }
 
-            clear_background(
-                self.dungeon_manager.worlds[self.dungeon_manager.world_index].background_color,
-            );
+            let world = self.sim.dungeon_manager.worlds[self.sim.dungeon_manager.world_index];
+            clear_background(world.background_color);
             set_camera(&world_camera);
             clear_background(
-                self.dungeon_manager.worlds[self.dungeon_manager.world_index].background_color,
+                self.sim.dungeon_manager.worlds[self.sim.dungeon_manager.world_index]
+                    .background_color,
             );
             // draw background tiles
             let screen_tiles = SCREEN_HEIGHT as u32 / 16;
@@ -660,20 +282,20 @@ impl<'a> Ramble<'a> {
                         if y == 1 { 6.0 } else { 4.0 }
                     } else if y == 1 {
                         if x == door_start_x {
-                            8.0 + self.state.door_frame()
+                            8.0 + self.sim.state.door_frame()
                         } else if x == door_start_x + 1 {
-                            9.0 + self.state.door_frame()
+                            9.0 + self.sim.state.door_frame()
                         } else {
                             2.0
                         }
                     } else {
                         0.0
                     };
-                    self.assets.world.draw_sprite(
+                    self.assets.world_sheet(world.sheet).draw_sprite(
                         x as f32 * 16.0 + 8.0,
                         y as f32 * 16.0 + 8.0,
                         tile,
-                        self.dungeon_manager.world_index as f32,
+                        world.tiles,
                         None,
                     );
                 }
@@ -681,54 +303,72 @@ impl<'a> Ramble<'a> {
 
             let now = get_time();
 
-            if !self.ui_manager.inv_open && now - last >= 1.0 / 60.0 {
-                // update
-                last = now;
+            if !self.ui_manager.inv_open {
+                self.interact_pressed |= is_key_pressed(KeyCode::E);
+            }
 
-                match &mut self.state {
-                    GameState::GameOver(frames, win) => {
-                        *frames += 1;
-                        if *frames > 30 {
-                            set_default_camera();
-                            return *win;
-                        }
-                    }
-                    GameState::RoundActive => {
-                        self.update(mouse_x, mouse_y);
+            // run as many steps as fit in the time since the last frame,
+            // and keep the rest for the next frame
+            accumulator = (accumulator + now - last).min(TIMESTEP * MAX_STEPS_PER_FRAME as f64);
+            last = now;
+            let ticks = (accumulator / TIMESTEP) as u32;
+            accumulator -= ticks as f64 * TIMESTEP;
+
+            let moving = if self.playback.is_some() {
+                if is_key_pressed(KeyCode::Escape) {
+                    set_default_camera();
+                    return None;
+                }
+                self.update_playback(ticks)
+            } else if !self.ui_manager.inv_open {
+                for _ in 0..ticks {
+                    let input = self.read_input(mouse_x, mouse_y);
+                    let input = self.replay.record_step(&input);
+                    let was_between_rooms = matches!(self.sim.state, GameState::PostRound(..));
+                    self.sim.step(&input);
+                    self.floating_text.step(&self.sim.hits);
+                    let cleared =
+                        !was_between_rooms && matches!(self.sim.state, GameState::PostRound(..));
+                    if cleared || input.interact {
+                        self.autosave();
                     }
-                    GameState::PostRound(frame, _) => {
-                        if *frame != 40 {
-                            *frame = frame.saturating_add(1);
-                        }
-                        self.update(mouse_x, mouse_y);
-                    }
-                    GameState::PreRound(frame) => {
-                        let max = PREROUND_TRANSITION_TIME + PREROUND_GRACE_TIME;
-                        *frame += 1;
-                        if *frame < PREROUND_TRANSITION_TIME / 2 {
-                            let amt = *frame as f32 / (PREROUND_TRANSITION_TIME as f32 / 2.0);
-                            world_camera.offset.y = amt * 2.0;
-                        } else if *frame <= PREROUND_TRANSITION_TIME {
-                            let amt = ((*frame) - PREROUND_TRANSITION_TIME / 2) as f32
-                                / (PREROUND_TRANSITION_TIME as f32 / 2.0);
-                            world_camera.offset.y = amt * 2.0 - 2.0;
-                        }
-                        if *frame > max {
-                            world_camera.offset.y = 0.0;
-                            self.state = GameState::RoundActive;
-                        }
+                    if self.sim.finished().is_some() {
+                        break;
                     }
                 }
+                true
+            } else {
+                false
+            };
+            // how far the frame is between the last step and the next one
+            let alpha = if moving && self.settings.interpolate {
+                (accumulator / TIMESTEP) as f32
+            } else {
+                1.0
+            };
+            if let Some(win) = self.sim.finished() {
+                if self.playback.is_none() {
+                    save::delete_save();
+                }
+                set_default_camera();
+                return Some(win);
             }
+            world_camera.offset.y = self.sim.state.camera_offset();
 
-            if self.player.pos.y > 94.0 {
-                self.handle_item_shop()
+            // show where the replay was aiming instead of the real mouse
+            let (mouse_x, mouse_y) = match &self.playback {
+                Some(playback) => (playback.aim.x, playback.aim.y),
+                None => (mouse_x, mouse_y),
+            };
+
+            if self.sim.player.pos.y > 94.0 {
+                self.draw_item_shop()
             }
-            if self.state.should_draw() {
-                self.draw(mouse_x, mouse_y);
+            if self.sim.state.should_draw() {
+                self.draw(mouse_x, mouse_y, alpha);
             }
-            if self.player.pos.y <= 94.0 {
-                self.handle_item_shop()
+            if self.sim.player.pos.y <= 94.0 {
+                self.draw_item_shop()
             }
             set_camera(&self.ui_camera);
             clear_background(Color::from_rgba(0, 0, 0, 0));
@@ -759,7 +399,7 @@ impl<'a> Ramble<'a> {
                     ..Default::default()
                 },
             );
-            if let GameState::GameOver(frames, _) = &self.state {
+            if let GameState::GameOver(frames, _) = &self.sim.state {
                 draw_rectangle(
                     0.0,
                     0.0,
@@ -773,7 +413,73 @@ impl<'a> Ramble<'a> {
     }
 }
 
-type Class = (&'static str, Option<Item>, Option<Item>, Option<Item>);
+/// Options picked on the main menu
+#[derive(Clone, Copy)]
+struct Settings {
+    /// Draw movement between steps smoothly, instead of only where things were on the last step
+    interpolate: bool,
+    /// Show the damage of each hit floating up from the enemy
+    damage_numbers: bool,
+}
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            interpolate: true,
+            damage_numbers: true,
+        }
+    }
+}
+
+/// Generate a seed for a new run
+fn random_seed() -> u64 {
+    rand::gen_range(0, u32::MAX) as u64
+}
+
+/// Create the simulation a replay starts from, either a new run or the save it was resumed from
+fn replay_simulation<'a>(
+    assets: &'a Assets,
+    classes: &[Class],
+    replay: &Replay,
+) -> Result<Simulation<'a>, String> {
+    if let Some(save) = &replay.save {
+        return Simulation::from_save(&assets.all_items, &assets.all_enchantments, save);
+    }
+    let player = class_player(&classes[replay.class_index as usize % classes.len()]);
+    #[allow(unused_mut)]
+    let mut sim = Simulation::new(
+        &assets.all_items,
+        &assets.all_enchantments,
+        player,
+        replay.seed,
+    );
+
+    #[cfg(debug_assertions)]
+    {
+        for (index, item) in assets.all_items.iter().enumerate() {
+            let w = ((SCREEN_WIDTH - 40.0) / 14.0).floor();
+            sim.dropped_items.push((
+                Vec2::new(
+                    20.0 + ((index as f32) % w).floor() * 14.0,
+                    ((index as f32) / w).floor() * 14.0 + 48.0,
+                ),
+                item.clone(),
+            ));
+        }
+    }
+    Ok(sim)
+}
+
+/// Save replay of a finished run to the replays directory
+#[cfg(not(target_arch = "wasm32"))]
+fn save_replay(replay: &Replay) {
+    let dir = std::path::Path::new("replays");
+    let path = dir.join(format!("{}.replay", replay.seed));
+    if let Err(err) =
+        std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, replay.to_bytes()))
+    {
+        eprintln!("failed to save replay to {}: {err}", path.display());
+    }
+}
 
 fn window_conf() -> Conf {
     Conf {
@@ -785,37 +491,64 @@ fn window_conf() -> Conf {
 }
 #[macroquad::main(window_conf)]
 async fn main() {
-    // seed random
+    // seed random, only used to pick seeds for new runs
     rand::srand(macroquad::miniquad::date::now() as _);
 
     // define assets and classes
-    let assets = Assets::default();
-    let classes: Vec<Class> = vec![
-        (
-            "warrior",
-            Some(assets.get_item_by_name("longsword").clone()),
-            None,
-            Some(assets.get_item_by_name("chainmail").clone()),
-        ),
-        (
-            "sorcerer",
-            Some(assets.get_item_by_name("light ray").clone()),
-            None,
-            Some(assets.get_item_by_name("wizards robes").clone()),
-        ),
-        (
-            "archer",
-            Some(assets.get_item_by_name("bow").clone()),
-            Some(assets.get_item_by_name("archers hood").clone()),
-            Some(assets.get_item_by_name("leather tunic").clone()),
-        ),
-    ];
+    #[allow(unused_mut)]
+    let mut assets = Assets::default();
+    #[allow(unused_mut)]
+    let mut classes = get_classes(&assets.all_items).unwrap_or_else(|err| panic!("{err}"));
     let mut class_index: usize = 0;
-    let mut gameover_screen: Option<bool> = None;
+    // whether the run was won, and its replay
+    let mut gameover_screen: Option<(bool, Replay)> = None;
+    // page of the bestiary, if it's open
+    let mut bestiary_page: Option<usize> = None;
+    let mut seed_text = random_seed().to_string();
+    let mut seed_focused = false;
+    let mut saved_run = save::read_save();
+    let mut settings = Settings::default();
+
+    // watch a replay file passed with `--replay <path>`
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(index) = args.iter().position(|f| f == "--replay")
+            && let Some(path) = args.get(index + 1)
+        {
+            match std::fs::read(path)
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| Replay::from_bytes(&bytes))
+            {
+                Ok(replay) => match replay_simulation(&assets, &classes, &replay) {
+                    Ok(sim) => {
+                        let mut ramble =
+                            Ramble::new_playback(&assets, sim, replay.clone(), settings);
+                        if let Some(win) = ramble.run().await {
+                            gameover_screen = Some((win, replay));
+                        }
+                    }
+                    Err(err) => eprintln!("failed to load replay {path}: {err}"),
+                },
+                Err(err) => eprintln!("failed to load replay {path}: {err}"),
+            }
+        }
+    }
 
     // main menu
     let camera = create_camera(SCREEN_WIDTH, SCREEN_HEIGHT);
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    let mut watcher = AssetWatcher::new();
     loop {
+        #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+        if let Some(data) = hot_reload::reload(&assets, &watcher.changed_files()) {
+            assets.all_items = data.items.to_vec();
+            assets.all_enchantments = data.enchantments.to_vec();
+            match get_classes(&assets.all_items) {
+                Ok(new) => classes = new,
+                Err(err) => eprintln!("failed to reload classes: {err}"),
+            }
+        }
         let (screen_width, screen_height) = screen_size();
         let scale_factor = (screen_width / SCREEN_WIDTH).min(screen_height / SCREEN_HEIGHT);
         let horizontal_padding = (screen_width - SCREEN_WIDTH * scale_factor) / 2.0;
@@ -826,7 +559,8 @@ async fn main() {
         );
         set_camera(&camera);
         clear_background(BLACK);
-        if let Some(win) = gameover_screen {
+        if let Some((win, replay)) = &gameover_screen {
+            let win = *win;
             let scale = 2.0;
             let width = 3.0 * 16.0;
             let height = 16.0;
@@ -843,6 +577,31 @@ async fn main() {
             let x = (SCREEN_WIDTH - width * scale) / 2.0;
             draw_texture_ex(&assets.ui.texture, x, 4.0, WHITE, params);
 
+            let text = format!("seed: {}", replay.seed);
+            assets.draw_text(
+                &text,
+                (SCREEN_WIDTH - text.chars().count() as f32 * 4.0) / 2.0,
+                8.0 + height * scale,
+            );
+
+            if ui::draw_button(
+                "watch replay",
+                &assets,
+                x,
+                SCREEN_HEIGHT - 80.0,
+                width * scale,
+                mouse_x,
+                mouse_y,
+            ) {
+                let replay = replay.clone();
+                // the replay was recorded from this simulation, so it can't fail
+                let sim = replay_simulation(&assets, &classes, &replay).unwrap();
+                let mut ramble = Ramble::new_playback(&assets, sim, replay.clone(), settings);
+                if let Some(win) = ramble.run().await {
+                    gameover_screen = Some((win, replay));
+                }
+                continue;
+            }
             if ui::draw_button(
                 "return to menu",
                 &assets,
@@ -853,8 +612,62 @@ async fn main() {
                 mouse_y,
             ) {
                 gameover_screen = None;
+                seed_text = random_seed().to_string();
                 continue;
             }
+        } else if let Some(page) = &mut bestiary_page {
+            let enemies = worlds::enemies_in_worlds();
+            let pages = enemies.len().div_ceil(ui::BESTIARY_PAGE_SIZE).max(1);
+            *page = (*page).min(pages - 1);
+            let title = if pages > 1 {
+                format!("bestiary {}/{pages}", *page + 1)
+            } else {
+                "bestiary".to_string()
+            };
+            assets.draw_text(
+                &title,
+                (SCREEN_WIDTH - title.chars().count() as f32 * 4.0) / 2.0,
+                4.0,
+            );
+            ui::draw_bestiary(&enemies, *page, &assets);
+
+            let width = 3.0 * 16.0 * 2.0;
+            let x = (SCREEN_WIDTH - width) / 2.0;
+            if pages > 1 {
+                if ui::draw_button(
+                    ")",
+                    &assets,
+                    x + width + 2.0,
+                    SCREEN_HEIGHT - 10.0,
+                    6.0,
+                    mouse_x,
+                    mouse_y,
+                ) {
+                    *page = (*page + 1) % pages;
+                }
+                if ui::draw_button(
+                    "(",
+                    &assets,
+                    x - 8.0,
+                    SCREEN_HEIGHT - 10.0,
+                    6.0,
+                    mouse_x,
+                    mouse_y,
+                ) {
+                    *page = (*page + pages - 1) % pages;
+                }
+            }
+            if ui::draw_button(
+                "back",
+                &assets,
+                x,
+                SCREEN_HEIGHT - 10.0,
+                width,
+                mouse_x,
+                mouse_y,
+            ) {
+                bestiary_page = None;
+            }
         } else {
             let scale = 2.0;
             let width = 3.0 * 16.0;
@@ -872,14 +685,27 @@ async fn main() {
             let x = (SCREEN_WIDTH - width * scale) / 2.0;
             draw_texture_ex(&assets.ui.texture, x, 4.0, WHITE, params);
 
+            // list active mods to the left of the title
+            if !mods::MODS.is_empty() {
+                let mut text = String::from("mods:");
+                for m in mods::MODS.iter() {
+                    let name: String = m.name.to_lowercase().chars().take(18).collect();
+                    text.push_str(&format!("\n {name}"));
+                }
+                let problems = mods::problems().len();
+                if problems > 0 {
+                    text.push_str(&format!("\n\x02{problems} problems"));
+                }
+                assets.draw_text(&text, 2.0, 2.0);
+            }
+
             let preview_height = 100.0;
             let preview_y = 6.0 + height * scale;
             ui::draw_ui_rect(x, preview_y, width * scale, preview_height);
 
-            let mut player = Player::new(Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0));
-            let class_name;
-            (class_name, player.hand, player.helmet, player.chestplate) =
-                classes[class_index % classes.len()].clone();
+            let class = &classes[class_index % classes.len()];
+            let player = class_player(class);
+            let class_name = class.0;
             assets.draw_text(
                 class_name,
                 x + (width * scale - class_name.chars().count() as f32 * 4.0) / 2.0,
@@ -899,6 +725,18 @@ async fn main() {
                 Some(&params),
             );
 
+            ui::draw_number_field(
+                "seed: ",
+                &mut seed_text,
+                &mut seed_focused,
+                &assets,
+                x + 2.0,
+                preview_y + preview_height - 10.0,
+                width * scale - 4.0,
+                mouse_x,
+                mouse_y,
+            );
+
             if ui::draw_button(
                 "select and start",
                 &assets,
@@ -909,11 +747,93 @@ async fn main() {
                 mouse_y,
             ) {
                 // run game
-                let mut ramble = Ramble::new(&assets, player);
-                gameover_screen = Some(ramble.run().await);
+                let seed = seed_text.parse().unwrap_or_else(|_| random_seed());
+                let class = (class_index % classes.len()) as u8;
+                let replay = Replay::new(seed, class);
+                let sim = replay_simulation(&assets, &classes, &replay).unwrap();
+                let mut ramble = Ramble::new(&assets, sim, replay, settings);
+                let win = ramble.run().await.unwrap_or_default();
+                #[cfg(not(target_arch = "wasm32"))]
+                save_replay(&ramble.replay);
+                gameover_screen = Some((win, ramble.replay));
+                saved_run = save::read_save();
+                seed_focused = false;
                 class_index = 0;
                 continue;
             }
+            if let Some(save) = &saved_run
+                && ui::draw_button(
+                    "continue run",
+                    &assets,
+                    x,
+                    SCREEN_HEIGHT - 48.0,
+                    width * scale,
+                    mouse_x,
+                    mouse_y,
+                )
+            {
+                match Simulation::from_save(&assets.all_items, &assets.all_enchantments, save) {
+                    Ok(sim) => {
+                        let mut replay = Replay::new(sim.seed, 0);
+                        replay.save = Some(save.clone());
+                        let mut ramble = Ramble::new(&assets, sim, replay, settings);
+                        let win = ramble.run().await.unwrap_or_default();
+                        #[cfg(not(target_arch = "wasm32"))]
+                        save_replay(&ramble.replay);
+                        gameover_screen = Some((win, ramble.replay));
+                        seed_focused = false;
+                    }
+                    Err(err) => {
+                        eprintln!("failed to load save: {err}");
+                        save::delete_save();
+                    }
+                }
+                saved_run = save::read_save();
+                continue;
+            }
+            let smoothing = if settings.interpolate {
+                "smooth: on"
+            } else {
+                "smooth: off"
+            };
+            if ui::draw_button(
+                smoothing,
+                &assets,
+                2.0,
+                SCREEN_HEIGHT - 10.0,
+                60.0,
+                mouse_x,
+                mouse_y,
+            ) {
+                settings.interpolate = !settings.interpolate;
+            }
+            let damage_numbers = if settings.damage_numbers {
+                "numbers: on"
+            } else {
+                "numbers: off"
+            };
+            if ui::draw_button(
+                damage_numbers,
+                &assets,
+                2.0,
+                SCREEN_HEIGHT - 20.0,
+                60.0,
+                mouse_x,
+                mouse_y,
+            ) {
+                settings.damage_numbers = !settings.damage_numbers;
+            }
+            if ui::draw_button(
+                "bestiary",
+                &assets,
+                SCREEN_WIDTH - 62.0,
+                SCREEN_HEIGHT - 10.0,
+                60.0,
+                mouse_x,
+                mouse_y,
+            ) {
+                bestiary_page = Some(0);
+            }
             if ui::draw_button(
                 "exit game",
                 &assets,
@@ -0,0 +1,131 @@
+//! Mods are folders in a `mods` folder next to the executable, laid out like `assets/`.
+//! They can add to or override the data files, layouts and spritesheets, and are loaded in
+//! order of their folder names, so a mod overrides the ones before it.
+
+use std::{
+    path::PathBuf,
+    sync::{LazyLock, Mutex},
+};
+
+use crate::{assets::SheetId, data::DataFile};
+
+pub struct Mod {
+    /// Name of the mod's folder
+    pub name: String,
+    pub dir: PathBuf,
+}
+impl Mod {
+    /// Read a file in the mod's folder, given its path in there. None if the mod doesn't have it.
+    pub fn read(&self, path: &str) -> Option<Result<Vec<u8>, String>> {
+        let path = self.dir.join(path);
+        path.is_file().then(|| {
+            std::fs::read(&path).map_err(|err| format!("failed to read {}: {err}", path.display()))
+        })
+    }
+    /// Path of a file in the mod's folder, as shown in errors
+    pub fn path(&self, path: &str) -> String {
+        format!("mods/{}/{path}", self.name)
+    }
+}
+
+/// Mods in load order. Always empty on web.
+pub static MODS: LazyLock<Vec<Mod>> = LazyLock::new(find_mods);
+
+static PROBLEMS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn find_mods() -> Vec<Mod> {
+    #[cfg(target_arch = "wasm32")]
+    return Vec::new();
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let Some(dir) = std::env::current_exe()
+            .ok()
+            .and_then(|f| Some(f.parent()?.join("mods")))
+        else {
+            return Vec::new();
+        };
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut mods: Vec<Mod> = entries
+            .flatten()
+            .filter(|f| f.path().is_dir())
+            .map(|f| Mod {
+                name: f.file_name().to_string_lossy().into_owned(),
+                dir: f.path(),
+            })
+            .collect();
+        mods.sort_by(|a, b| a.name.cmp(&b.name));
+        mods
+    }
+}
+
+/// Report a conflict between mods, or a part of a mod that couldn't be loaded.
+/// Problems are printed the first time they're reported, and listed on the main menu.
+pub fn report(problem: String) {
+    let mut problems = PROBLEMS.lock().unwrap();
+    if !problems.contains(&problem) {
+        eprintln!("{problem}");
+        problems.push(problem);
+    }
+}
+
+/// Every problem reported so far
+pub fn problems() -> Vec<String> {
+    PROBLEMS.lock().unwrap().clone()
+}
+
+/// Returns the last mod's version of a file that replaces the game's, along with the mod's
+/// name. If several mods have the file, it's reported as a conflict.
+pub fn override_file(path: &str) -> Option<(String, Vec<u8>)> {
+    let mut found = None;
+    for m in MODS.iter() {
+        let Some(bytes) = m.read(path) else {
+            continue;
+        };
+        let bytes = match bytes {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                report(err);
+                continue;
+            }
+        };
+        if let Some((other, _)) = &found {
+            report(format!(
+                "mods '{other}' and '{}' both replace {path}, '{}' is used",
+                m.name, m.name
+            ));
+        }
+        found = Some((m.name.clone(), bytes));
+    }
+    found
+}
+
+/// The game's data file with the given name, followed by the same file from each mod that
+/// has one. Sprites in a mod's file are on its own copy of the spritesheet `sheet` if it has one.
+pub fn data_files(name: &str, sheet: &str, builtin: &[u8]) -> Vec<DataFile> {
+    let mut files = vec![DataFile {
+        path: name.to_string(),
+        text: String::from_utf8_lossy(builtin).into_owned(),
+        from_mod: None,
+        sheet: SheetId::BuiltIn,
+    }];
+    for (index, m) in MODS.iter().enumerate() {
+        let path = format!("data/{name}");
+        match m.read(&path) {
+            Some(Ok(bytes)) => files.push(DataFile {
+                path: m.path(&path),
+                text: String::from_utf8_lossy(&bytes).into_owned(),
+                from_mod: Some(m.name.clone()),
+                sheet: if m.dir.join(sheet).is_file() {
+                    SheetId::Mod(index)
+                } else {
+                    SheetId::BuiltIn
+                },
+            }),
+            Some(Err(err)) => report(err),
+            None => {}
+        }
+    }
+    files
+}
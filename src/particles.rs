@@ -10,7 +10,7 @@ pub struct ParticleContext {
     pub origin: Vec2,
 }
 
-pub static LIGHT_RAY: Particle = &|ctx, assets| {
+pub static LIGHT_RAY: Particle = &|ctx, _assets| {
     draw_line(ctx.origin.x, ctx.origin.y, ctx.pos.x, ctx.pos.y, 4.0, WHITE);
 };
 pub static STAR_EXPLOSION: Particle = &|ctx, assets| {
@@ -19,7 +19,7 @@ pub static STAR_EXPLOSION: Particle = &|ctx, assets| {
         .particles
         .draw_sprite(ctx.pos.x, ctx.pos.y, anim, 48.0 / 16.0, None);
 };
-pub static ACID_PUDDLE: Particle = &|ctx, assets| {
+pub static ACID_PUDDLE: Particle = &|ctx, _assets| {
     draw_ellipse(
         ctx.pos.x,
         ctx.pos.y,
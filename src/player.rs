@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use std::{cell::OnceCell, collections::HashMap, sync::Arc};
 
 use crate::{
     assets::Assets,
-    items::{Item, ItemType},
+    enemy::StatusEffectType,
+    items::{ActiveItem, ChargeProfile, Item, ItemType, get_item_by_name},
     projectiles::{DamageType, Projectile},
     utils::*,
 };
@@ -11,17 +12,27 @@ use macroquad::prelude::*;
 use struct_iterable::Iterable;
 
 pub fn get_movement_vector() -> Vec2 {
+    movement_vector(
+        is_key_down(KeyCode::A),
+        is_key_down(KeyCode::D),
+        is_key_down(KeyCode::W),
+        is_key_down(KeyCode::S),
+    )
+}
+
+/// Normalized movement vector from which directions are held
+pub fn movement_vector(left: bool, right: bool, up: bool, down: bool) -> Vec2 {
     let mut move_vector = Vec2::new(0.0, 0.0);
-    if is_key_down(KeyCode::A) {
+    if left {
         move_vector.x -= 1.0
     }
-    if is_key_down(KeyCode::D) {
+    if right {
         move_vector.x += 1.0
     }
-    if is_key_down(KeyCode::W) {
+    if up {
         move_vector.y -= 1.0
     }
-    if is_key_down(KeyCode::S) {
+    if down {
         move_vector.y += 1.0
     }
     move_vector.try_normalize().unwrap_or(Vec2::ZERO)
@@ -40,6 +51,20 @@ pub enum ChaosCurse {
     RepairArmor,
 }
 
+/// Projectile spawned on hit, and the damage it deals
+pub type OnHitEffect = (Projectile, HashMap<DamageType, f32>);
+
+/// What a hit needs for an on hit effect to trigger
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Sequence)]
+pub enum OnHitTrigger {
+    /// Any hit, including hitting a wall
+    Hit,
+    /// A hit that deals damage of this type
+    Damage(DamageType),
+    /// A critical hit on an enemy
+    Crit,
+}
+
 #[derive(Default, Clone, Iterable)]
 pub struct Stats {
     pub move_speed: f32,
@@ -48,11 +73,31 @@ pub struct Stats {
     pub attack_delay: f32,
     pub roll_delay: f32,
     pub roll_delay_mod: f32,
+    /// Multiplier of the active item's cooldown
+    pub cooldown_mod: f32,
+    /// Fraction of a hit's damage dealt again if its knockback slams the enemy in to a wall
+    pub impact_damage_mod: f32,
+    /// Chance of a hit being critical
+    pub crit_chance: f32,
+    /// Critical hits deal this much more damage
+    pub crit_damage_mod: f32,
+    /// Extra enemies the player's projectiles can pass through
+    pub pierce: u16,
+    /// Extra times the player's projectiles bounce off the edges of the room
+    pub bounces: u16,
+    /// Extra projectiles each attack fires, fanned out around the aim direction
+    pub projectiles: u16,
+    /// Angle in degrees between the projectiles of an attack
+    pub spread: f32,
+    /// Extra shots each attack fires after the first, [BURST_DELAY] frames apart
+    pub burst: u16,
     pub max_lives: u16,
     pub lives: u16,
     pub damage: HashMap<DamageType, f32>,
     pub damage_modifiers: HashMap<DamageType, f32>,
-    pub on_hit_effects: HashMap<Option<DamageType>, Vec<(Projectile, HashMap<DamageType, f32>)>>,
+    pub on_hit_effects: HashMap<OnHitTrigger, Vec<OnHitEffect>>,
+    /// Chance of applying each status effect to an enemy when dealing damage of a type
+    pub status_chances: HashMap<DamageType, HashMap<StatusEffectType, f32>>,
 }
 impl Stats {
     pub fn to_text(&self) -> Vec<String> {
@@ -94,7 +139,7 @@ impl Stats {
                 if *f == f32::default() {
                     continue;
                 }
-                let is_mod = k.ends_with("_mod");
+                let is_mod = k.ends_with("_mod") || k.ends_with("_chance");
                 let mut formatted = k.replace("_", " ");
                 if is_mod {
                     formatted = formatted.trim_end_matches(" mod").to_string();
@@ -112,22 +157,53 @@ impl Stats {
                 }
             }
         }
-        lines.append(&mut &mut damage_to_text(&self.damage));
+        if self.pierce != 0 {
+            lines.push(format!("pierce: +{}", self.pierce));
+        }
+        if self.bounces != 0 {
+            lines.push(format!("bounces: +{}", self.bounces));
+        }
+        if self.projectiles != 0 {
+            lines.push(format!("\x03projectiles\x01: +{}", self.projectiles));
+        }
+        if self.burst != 0 {
+            lines.push(format!("\x03burst\x01: +{}", self.burst));
+        }
+        lines.append(&mut damage_to_text(&self.damage));
         lines.append(&mut damage_modifiers_to_text(&self.damage_modifiers));
         for (k, v) in &self.on_hit_effects {
             if v.is_empty() {
                 continue;
             }
-            let text = k
-                .map(|f| format!("{f:?} dmg dealt").to_lowercase())
-                .unwrap_or("hit".into());
+            let text = match k {
+                OnHitTrigger::Hit => "hit".into(),
+                OnHitTrigger::Damage(ty) => format!("{} dmg dealt", ty.to_text()),
+                OnHitTrigger::Crit => "crit".into(),
+            };
             let v = v
                 .iter()
-                .map(|f| damage_to_text(&f.1).join(&String::from("")))
+                .map(|f| damage_to_text(&f.1).join(""))
                 .collect::<Vec<_>>()
                 .join(&String::from("\n& "));
             lines.push(format!("\x01on \x00{}\x01, deal:\n {}", text, v));
         }
+        for ty in enum_iterator::all::<DamageType>() {
+            let Some(chances) = self.status_chances.get(&ty) else {
+                continue;
+            };
+            for effect in enum_iterator::all::<StatusEffectType>() {
+                if let Some(chance) = chances.get(&effect)
+                    && *chance != 0.0
+                {
+                    lines.push(format!(
+                        "\x00{} chance\x01: {}% on {}",
+                        effect.to_text(),
+                        (chance * 100.0).round(),
+                        ty.to_text()
+                    ));
+                }
+            }
+        }
         lines
     }
     pub fn merge(&mut self, other: &Stats) {
@@ -137,6 +213,15 @@ impl Stats {
         self.attack_delay_mod += other.attack_delay_mod;
         self.attack_delay += other.attack_delay;
         self.roll_delay_mod += other.roll_delay_mod;
+        self.cooldown_mod += other.cooldown_mod;
+        self.impact_damage_mod += other.impact_damage_mod;
+        self.crit_chance += other.crit_chance;
+        self.crit_damage_mod += other.crit_damage_mod;
+        self.pierce += other.pierce;
+        self.bounces += other.bounces;
+        self.projectiles += other.projectiles;
+        self.spread += other.spread;
+        self.burst += other.burst;
         for (k, v) in &other.damage_modifiers {
             if self.damage_modifiers.contains_key(k) {
                 self.damage_modifiers
@@ -159,6 +244,36 @@ impl Stats {
                 self.on_hit_effects.insert(*k, v.clone());
             }
         }
+        for (k, v) in &other.status_chances {
+            let chances = self.status_chances.entry(*k).or_default();
+            for (effect, chance) in v {
+                *chances.entry(*effect).or_default() += chance;
+            }
+        }
+    }
+    /// If every stat is the same as another's. On hit effects are only compared by how many
+    /// there are of each trigger
+    fn same_as(&self, other: &Stats) -> bool {
+        let numbers = self.iter().zip(other.iter()).all(|((_, a), (_, b))| {
+            if let (Some(a), Some(b)) = (a.downcast_ref::<f32>(), b.downcast_ref::<f32>()) {
+                a == b || a.is_nan() && b.is_nan()
+            } else if let (Some(a), Some(b)) = (a.downcast_ref::<u16>(), b.downcast_ref::<u16>()) {
+                a == b
+            } else {
+                true
+            }
+        });
+        numbers
+            && self.damage == other.damage
+            && self.damage_modifiers == other.damage_modifiers
+            && self.status_chances == other.status_chances
+            && self.on_hit_effects.len() == other.on_hit_effects.len()
+            && self.on_hit_effects.iter().all(|(trigger, effects)| {
+                other
+                    .on_hit_effects
+                    .get(trigger)
+                    .is_some_and(|f| f.len() == effects.len())
+            })
     }
     pub fn apply_modifiers(&mut self) {
         self.move_speed *= 1.0 + self.move_speed_mod;
@@ -167,29 +282,84 @@ impl Stats {
     }
 }
 
+/// Name, weapon, helmet and chestplate a class starts with
+pub type Class = (&'static str, Option<Item>, Option<Item>, Option<Item>);
+
+pub fn get_classes(items: &[Item]) -> Result<Vec<Class>, String> {
+    let item = |name| get_item_by_name(items, name).cloned().map(Some);
+    Ok(vec![
+        ("warrior", item("longsword")?, None, item("chainmail")?),
+        ("sorcerer", item("light ray")?, None, item("wizards robes")?),
+        (
+            "archer",
+            item("bow")?,
+            item("archers hood")?,
+            item("leather tunic")?,
+        ),
+    ])
+}
+
+pub fn class_player(class: &Class) -> Player {
+    let mut player = Player::new(Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0));
+    (_, player.hand, player.helmet, player.chestplate) = class.clone();
+    player
+}
+
+/// A slot the player can hold an item in
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InventorySlot {
+    Helmet,
+    Chestplate,
+    Hand,
+    Active,
+    Talisman(usize),
+    Inventory(usize),
+}
+
 #[derive(Default)]
 pub struct Player {
     pub pos: Vec2,
-    pub internal_stats: Stats,
-    pub curses: Vec<ChaosCurse>,
-    pub inventory: Vec<Option<Item>>,
-    pub talismans: Vec<Option<Item>>,
-    pub helmet: Option<Item>,
-    pub chestplate: Option<Item>,
-    pub hand: Option<Item>,
+    /// Position before the last step, used to interpolate drawing between steps
+    pub prev_pos: Vec2,
+    /// Stats of the player before their items. Changed through [Player::internal_stats_mut],
+    /// which invalidates the cached stats, as do the other ways of changing the items and curses
+    internal_stats: Stats,
+    /// Stats with the items merged in, worked out again the first time they're needed after
+    /// being invalidated
+    cached_stats: OnceCell<Arc<Stats>>,
+    curses: Vec<ChaosCurse>,
+    inventory: Vec<Option<Item>>,
+    talismans: Vec<Option<Item>>,
+    helmet: Option<Item>,
+    chestplate: Option<Item>,
+    hand: Option<Item>,
+    active: Option<Item>,
+    /// Item picked up by the cursor in the inventory
+    cursor_item: Option<Item>,
     pub moving: bool,
     pub anim_frame: f32,
     pub attack_counter: f32,
     pub invuln_frames: u8,
     pub roll_counter: f32,
+    /// Frames until the active item can be used again
+    pub active_counter: f32,
+    /// Frames left of the active item's shield, which blocks all damage
+    pub shield_frames: u16,
     /// Info about current roll. First value is roll frames, if zero, player is not rolling.
     /// Second is roll direction.
     pub roll: (u8, Vec2),
+    /// Shots left in the current burst, and frames until the next one
+    pub burst: (u16, u8),
+    /// Frames left of the flash shown after a perfect dodge
+    pub perfect_dodge_frames: u8,
+    /// Frames the held weapon's attack has been charging for. None if it isn't charging
+    pub charge: Option<u16>,
 }
 impl Player {
     pub fn new(pos: Vec2) -> Self {
         Self {
             pos,
+            prev_pos: pos,
             inventory: vec![None; INV_SLOTS],
             talismans: vec![None; 3],
             internal_stats: Stats {
@@ -197,6 +367,7 @@ impl Player {
                 lives: 3,
                 move_speed: 1.5,
                 roll_delay: 60.0,
+                crit_damage_mod: 0.5,
                 ..Default::default()
             },
             ..Default::default()
@@ -204,16 +375,105 @@ impl Player {
     }
     pub fn regen(&mut self) {
         self.internal_stats.lives = self.internal_stats.max_lives;
+        self.invalidate_stats();
     }
     pub fn repair_armor(&mut self) {
-        for armor in [&mut self.chestplate, &mut self.helmet] {
-            if let Some(armor) = armor {
-                armor.internal_stats.lives = armor.internal_stats.max_lives;
-            }
+        for armor in [&mut self.chestplate, &mut self.helmet]
+            .into_iter()
+            .flatten()
+        {
+            armor.internal_stats.lives = armor.internal_stats.max_lives;
+        }
+        self.invalidate_stats();
+    }
+    /// Stats of the player before their items
+    pub fn internal_stats(&self) -> &Stats {
+        &self.internal_stats
+    }
+    /// Change the stats of the player before their items
+    pub fn internal_stats_mut(&mut self) -> &mut Stats {
+        self.invalidate_stats();
+        &mut self.internal_stats
+    }
+    pub fn curses(&self) -> &[ChaosCurse] {
+        &self.curses
+    }
+    pub fn add_curse(&mut self, curse: ChaosCurse) {
+        self.curses.push(curse);
+        self.invalidate_stats();
+    }
+    pub fn inventory(&self) -> &[Option<Item>] {
+        &self.inventory
+    }
+    pub fn talismans(&self) -> &[Option<Item>] {
+        &self.talismans
+    }
+    pub fn cursor_item(&self) -> Option<&Item> {
+        self.cursor_item.as_ref()
+    }
+    pub fn set_cursor_item(&mut self, item: Option<Item>) -> Option<Item> {
+        std::mem::replace(&mut self.cursor_item, item)
+    }
+    pub fn slot(&self, slot: InventorySlot) -> Option<&Item> {
+        match slot {
+            InventorySlot::Helmet => self.helmet.as_ref(),
+            InventorySlot::Chestplate => self.chestplate.as_ref(),
+            InventorySlot::Hand => self.hand.as_ref(),
+            InventorySlot::Active => self.active.as_ref(),
+            InventorySlot::Talisman(index) => self.talismans.get(index)?.as_ref(),
+            InventorySlot::Inventory(index) => self.inventory.get(index)?.as_ref(),
+        }
+    }
+    /// Change the item in a slot. None if the slot doesn't exist
+    pub fn slot_mut(&mut self, slot: InventorySlot) -> Option<&mut Option<Item>> {
+        self.invalidate_stats();
+        Some(match slot {
+            InventorySlot::Helmet => &mut self.helmet,
+            InventorySlot::Chestplate => &mut self.chestplate,
+            InventorySlot::Hand => &mut self.hand,
+            InventorySlot::Active => &mut self.active,
+            InventorySlot::Talisman(index) => self.talismans.get_mut(index)?,
+            InventorySlot::Inventory(index) => self.inventory.get_mut(index)?,
+        })
+    }
+    /// Change every item the player has, including the one on the cursor
+    pub fn items_mut(&mut self) -> impl Iterator<Item = &mut Item> {
+        self.invalidate_stats();
+        [
+            &mut self.helmet,
+            &mut self.chestplate,
+            &mut self.hand,
+            &mut self.active,
+            &mut self.cursor_item,
+        ]
+        .into_iter()
+        .chain(self.talismans.iter_mut())
+        .chain(self.inventory.iter_mut())
+        .flatten()
+    }
+    /// Swap the cursor item with the item in a slot.
+    /// Does nothing if the cursor item doesn't fit in the slot.
+    pub fn swap_cursor_item(&mut self, slot: InventorySlot) {
+        let fits = |ty: &ItemType| match slot {
+            InventorySlot::Helmet => matches!(ty, ItemType::Helmet),
+            InventorySlot::Chestplate => matches!(ty, ItemType::Chestplate),
+            InventorySlot::Hand => matches!(ty, ItemType::Held(_)),
+            InventorySlot::Active => matches!(ty, ItemType::Active(_)),
+            InventorySlot::Talisman(_) => matches!(ty, ItemType::Talisman),
+            InventorySlot::Inventory(_) => true,
+        };
+        if self.cursor_item.as_ref().is_some_and(|f| !fits(&f.ty)) {
+            return;
+        }
+        let mut cursor_item = self.cursor_item.take();
+        if let Some(target) = self.slot_mut(slot) {
+            std::mem::swap(&mut cursor_item, target);
         }
+        self.cursor_item = cursor_item;
     }
     /// Panics if inventory full
     pub fn give_item(&mut self, item: Item) {
+        self.invalidate_stats();
         match &item.ty {
             ItemType::Chestplate => {
                 if self.chestplate.is_none() {
@@ -233,6 +493,12 @@ impl Player {
                     return;
                 }
             }
+            ItemType::Active(_) => {
+                if self.active.is_none() {
+                    self.active = Some(item);
+                    return;
+                }
+            }
             ItemType::Talisman => {
                 for t in self.talismans.iter_mut() {
                     if t.is_none() {
@@ -260,6 +526,7 @@ impl Player {
             ItemType::Chestplate => self.chestplate.is_none(),
             ItemType::Helmet => self.helmet.is_none(),
             ItemType::Held(_) => self.hand.is_none(),
+            ItemType::Active(_) => self.active.is_none(),
             ItemType::Talisman => {
                 let mut y = false;
                 for t in self.talismans.iter() {
@@ -286,29 +553,79 @@ impl Player {
         }
         false
     }
-    pub fn stats(&self) -> Stats {
+    /// The player's stats with their equipped items. Shared with the projectiles fired with
+    /// them, until something changes and [Player::invalidate_stats] is called
+    pub fn stats(&self) -> Arc<Stats> {
+        let stats = self
+            .cached_stats
+            .get_or_init(|| Arc::new(self.fresh_stats()))
+            .clone();
+        debug_assert!(
+            stats.same_as(&self.fresh_stats()),
+            "the player changed without invalidating their stats"
+        );
+        stats
+    }
+    /// Work the stats out from the items, without the cache
+    fn fresh_stats(&self) -> Stats {
         let mut stats = self.internal_stats.clone();
-
-        let mut items = vec![&self.helmet, &self.chestplate, &self.hand];
-        let t = self.talismans.clone();
-        items.append(&mut t.iter().collect());
-        for item in items.into_iter().flatten() {
+        let items = [&self.helmet, &self.chestplate, &self.hand, &self.active];
+        for item in items.into_iter().chain(self.talismans.iter()).flatten() {
             stats.merge(&item.stats());
         }
         stats.apply_modifiers();
         stats
     }
+    /// Make [Player::stats] work the stats out again the next time they're needed
+    pub fn invalidate_stats(&mut self) {
+        self.cached_stats.take();
+    }
+    /// The active item, if there is one in its slot
+    pub fn active_item(&self) -> Option<&ActiveItem> {
+        match &self.active.as_ref()?.ty {
+            ItemType::Active(active) => Some(active),
+            _ => None,
+        }
+    }
+    /// Frames the active item takes to be ready again after being used, after the player's
+    /// cooldown modifier. Always at least one frame, so it can't be used every step
+    pub fn active_cooldown(&self) -> Option<f32> {
+        let cooldown = self.active_item()?.cooldown;
+        Some((cooldown * (1.0 + self.stats().cooldown_mod)).max(1.0))
+    }
+    /// The held weapon's charge profile, if it has one
+    pub fn weapon_charge(&self) -> Option<&ChargeProfile> {
+        match &self.hand.as_ref()?.ty {
+            ItemType::Held(weapon) => weapon.charge.as_ref(),
+            _ => None,
+        }
+    }
+    /// The held weapon's charge profile, if it's charging
+    pub fn charging(&self) -> Option<&ChargeProfile> {
+        self.charge.and(self.weapon_charge())
+    }
+    /// How far the held weapon's attack is charged, from 0 to 1
+    pub fn charge_fraction(&self) -> Option<f32> {
+        let profile = self.charging()?;
+        Some((self.charge? as f32 / profile.frames as f32).min(1.0))
+    }
+    /// Dodging a projectile this early in to a roll is a perfect dodge
+    pub fn can_perfect_dodge(&self) -> bool {
+        self.roll.0 > ROLL_FRAMES - PERFECT_DODGE_WINDOW && self.perfect_dodge_frames == 0
+    }
     pub fn can_take_damage(&self) -> bool {
-        self.roll.0 == 0 && self.invuln_frames == 0
+        self.roll.0 == 0 && self.invuln_frames == 0 && self.shield_frames == 0
     }
     pub fn damage(&mut self) -> bool {
         self.invuln_frames = 100;
+        self.invalidate_stats();
         // find where to take heart
         let mut items: Vec<&mut Option<Item>> = self.talismans.iter_mut().collect();
         items.append(&mut vec![
             &mut self.helmet,
             &mut self.chestplate,
             &mut self.hand,
+            &mut self.active,
         ]);
         for item in items {
             if let Some(item) = item
@@ -335,14 +652,22 @@ impl Player {
 
         // draw armor
         if let Some(chestplate) = &self.chestplate {
-            assets
-                .items
-                .draw_sprite(x, y, chestplate.sprite_x, chestplate.sprite_y, draw_params);
+            assets.item_sheet(chestplate.sheet).draw_sprite(
+                x,
+                y,
+                chestplate.sprite_x,
+                chestplate.sprite_y,
+                draw_params,
+            );
         }
         if let Some(helmet) = &self.helmet {
-            assets
-                .items
-                .draw_sprite(x, y, helmet.sprite_x, helmet.sprite_y, draw_params);
+            assets.item_sheet(helmet.sheet).draw_sprite(
+                x,
+                y,
+                helmet.sprite_x,
+                helmet.sprite_y,
+                draw_params,
+            );
         }
     }
     pub fn draw(&self, assets: &Assets, mouse_x: f32, mouse_y: f32) {
@@ -378,6 +703,36 @@ impl Player {
 
         gl_use_default_material();
 
+        // draw perfect dodge flash, a ring growing out from the player
+        if self.perfect_dodge_frames > 0 {
+            let progress = 1.0 - self.perfect_dodge_frames as f32 / PERFECT_DODGE_FRAMES as f32;
+            draw_circle_lines(
+                self.pos.x,
+                self.pos.y,
+                6.0 + progress * 10.0,
+                1.0,
+                Color::from_vec(COLORS[3]),
+            );
+        }
+
+        // draw shield bubble, which flickers as it's about to run out
+        if self.shield_frames > 30 || (self.shield_frames / 4) % 2 == 1 {
+            draw_circle_lines(self.pos.x, self.pos.y, 9.0, 1.0, Color::from_vec(COLORS[3]));
+        }
+
+        // draw charge meter under the player, which turns white once it's full
+        if let Some(charge) = self.charge_fraction() {
+            let color = if charge >= 1.0 { COLORS[0] } else { COLORS[3] };
+            draw_rectangle(x - 6.0, y + 9.0, 12.0, 2.0, Color::from_vec(COLORS[1]));
+            draw_rectangle(
+                x - 6.0,
+                y + 9.0,
+                (12.0 * charge).floor(),
+                2.0,
+                Color::from_vec(color),
+            );
+        }
+
         // draw held item
         if let Some(held) = &self.hand {
             let delta = Vec2::new(mouse_x, mouse_y) - self.pos;
@@ -389,7 +744,7 @@ impl Player {
                 ..Default::default()
             };
             let offset = delta.normalize() * 12.0;
-            assets.items.draw_sprite(
+            assets.item_sheet(held.sheet).draw_sprite(
                 x + offset.x,
                 y + offset.y + 2.0,
                 held.sprite_x,
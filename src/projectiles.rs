@@ -1,14 +1,18 @@
-use std::{collections::HashMap, u16};
+use std::{collections::HashMap, f32::consts::PI, sync::Arc};
 
+use enum_iterator::Sequence;
 use macroquad::prelude::*;
 
 use crate::{
     assets::Assets,
     particles::{self, *},
-    player::Stats,
+    player::{OnHitTrigger, Stats},
 };
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+/// How close an enemy has to be for a homing projectile to turn towards it
+pub const HOMING_RADIUS: f32 = 96.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Sequence)]
 pub enum DamageType {
     Slashing,
     Piercing,
@@ -17,7 +21,7 @@ pub enum DamageType {
     Holy,
 }
 impl DamageType {
-    pub fn to_text(&self) -> &'static str {
+    pub fn to_text(self) -> &'static str {
         match self {
             DamageType::Slashing => "slashing",
             DamageType::Piercing => "piercing",
@@ -26,6 +30,19 @@ impl DamageType {
             DamageType::Holy => "holy",
         }
     }
+    pub fn from_text(text: &str) -> Option<Self> {
+        enum_iterator::all::<DamageType>().find(|f| f.to_text() == text)
+    }
+    /// Color code [Assets::draw_text] draws text of this damage type in
+    pub fn text_color(self) -> &'static str {
+        match self {
+            DamageType::Slashing => "\x00",
+            DamageType::Piercing => "\x03",
+            DamageType::Fire => "\x06",
+            DamageType::Unholy => "\x08",
+            DamageType::Holy => "\x07",
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -39,45 +56,160 @@ impl Default for DrawType {
     }
 }
 
-#[derive(Clone, Default)]
+/// Something a projectile does on top of flying forwards, with a hook for each point in its
+/// life. Projectiles are built out of a list of these.
+#[derive(Clone)]
+pub enum ProjectileBehavior {
+    /// Loses this fraction of its speed every frame. Starts faster if the player's moving the
+    /// way it's fired, more so the more drag it has.
+    Drag(f32),
+    /// Slows down until it turns around, and breaks once it's back where it was fired from
+    Boomerang,
+    /// Turns towards the closest enemy within [HOMING_RADIUS] it hasn't hit yet, or the player
+    /// if an enemy fired it, by this many radians per frame
+    Homing(f32),
+    /// Spins by this many radians per frame. Only changes how it's drawn
+    Spin(f32),
+    /// Leaves a copy of a projectile behind every this many frames
+    Trail(Box<Projectile>, u16),
+    /// Breaks in to this many copies of a projectile, fanned out around it, when its lifetime
+    /// runs out
+    SplitOnExpire(Box<Projectile>, u8),
+    /// Loses this fraction of its speed every time it passes through an enemy
+    SlowOnHit(f32),
+    /// Stops where it hits a wall instead of breaking, until its lifetime runs out
+    Stick,
+    /// Passes through enemies without hitting them, so it only does anything when it expires
+    Fuse,
+}
+impl ProjectileBehavior {
+    fn spawn(&self, projectile: &mut Projectile, move_vector: Vec2) {
+        if let ProjectileBehavior::Drag(drag) = self {
+            projectile.speed += (25.0 * drag) * (projectile.direction.dot(move_vector).max(0.0));
+        }
+    }
+    /// Returns false if the projectile should break
+    fn tick(
+        &self,
+        projectile: &mut Projectile,
+        target: Option<Vec2>,
+        spawned: &mut Vec<Projectile>,
+    ) -> bool {
+        match self {
+            ProjectileBehavior::Drag(drag) => {
+                projectile.speed = projectile.speed.lerp(0.0, *drag);
+            }
+            ProjectileBehavior::Homing(rate) => {
+                if let Some(target) = target {
+                    projectile.steer_towards(target, *rate);
+                }
+            }
+            ProjectileBehavior::Spin(rate) => projectile.rotation += rate,
+            ProjectileBehavior::Trail(trail, every) if projectile.life.is_multiple_of(*every) => {
+                spawned.push(projectile.child(trail));
+            }
+            _ => {}
+        }
+        true
+    }
+    /// Returns false if the projectile should break
+    fn moved(&self, projectile: &mut Projectile) -> bool {
+        if let ProjectileBehavior::Boomerang = self {
+            projectile.speed -= 0.1;
+            if projectile.speed < 0.0 && projectile.pos.distance(projectile.origin) <= 4.0 {
+                return false;
+            }
+        }
+        true
+    }
+    fn hit_enemy(&self, projectile: &mut Projectile) {
+        if let ProjectileBehavior::SlowOnHit(slow) = self {
+            projectile.speed *= 1.0 - slow;
+        }
+    }
+    /// Returns true if the projectile survives hitting the wall
+    fn hit_wall(&self, projectile: &mut Projectile) -> bool {
+        if let ProjectileBehavior::Stick = self {
+            projectile.speed = 0.0;
+            return true;
+        }
+        false
+    }
+    fn expire(&self, projectile: &Projectile, spawned: &mut Vec<Projectile>) {
+        if let ProjectileBehavior::SplitOnExpire(split, count) = self {
+            for i in 0..*count {
+                let mut split = projectile.child(split);
+                split.direction =
+                    Vec2::from_angle(i as f32 * (PI * 2.0) / *count as f32).rotate(split.direction);
+                spawned.push(split);
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Projectile {
     pub pos: Vec2,
+    /// Position before the last step, used to interpolate drawing between steps.
+    /// None if the projectile was spawned during the last step.
+    pub prev_pos: Option<Vec2>,
     pub origin: Vec2,
     pub direction: Vec2,
     pub speed: f32,
-    pub drag: f32,
     pub draw_type: DrawType,
     pub player_owned: bool,
-    pub boomerang: bool,
     pub life: u16,
     pub lifetime: u16,
     pub hit_enemies: Vec<usize>,
     pub parent_hit_enemies: Vec<usize>,
-    pub stats: Option<Stats>,
+    /// Stats of the player that fired it, shared with the other projectiles fired with them
+    pub stats: Option<Arc<Stats>>,
+    /// Damage it deals instead of the damage in its stats, for projectiles from on hit effects
+    /// and abilities
+    pub damage: Option<HashMap<DamageType, f32>>,
+    /// Multiplier on the damage it deals, which its on hit effects' projectiles deal too
+    pub damage_scale: f32,
     pub radius: f32,
+    /// Speed enemies hit are knocked back at, divided by their weight
+    pub knockback: f32,
+    /// Number of enemies the projectile can pass through after the first one it hits.
+    /// None if it passes through every enemy
+    pub pierce: Option<u16>,
+    /// Number of times the projectile bounces off the edges of the room instead of breaking
+    pub bounces: u16,
+    /// Angle the sprite is turned by on top of the direction it's going in
+    pub rotation: f32,
+    /// Destroys the enemy projectiles it touches, or sends back the reflectable ones
+    pub parries: bool,
+    /// Can be destroyed by the player's parrying projectiles
+    pub destructible: bool,
+    /// Sent back by the player's parrying projectiles instead of being destroyed
+    pub reflectable: bool,
+    pub behaviors: Vec<ProjectileBehavior>,
 }
 impl Projectile {
-    pub fn on_hit(&self) -> Vec<Projectile> {
+    /// Projectiles spawned by the on hit effects that trigger when this hits something
+    pub fn on_hit(&self, crit: bool) -> Vec<Projectile> {
         let mut new_projectiles = Vec::new();
         let Some(stats) = self.stats.clone() else {
             return new_projectiles;
         };
 
-        for (ty, items) in stats.on_hit_effects.into_iter() {
-            for (proj, damage) in items.into_iter() {
-                if ty.is_none_or(|ty| stats.damage.get(&ty).is_some_and(|f| *f > 0.0)) {
-                    let mut proj = proj.clone();
-                    proj.stats = self.stats.clone();
-                    if let Some(stats) = &mut proj.stats {
-                        stats.damage = damage.clone();
-                    }
-                    proj.parent_hit_enemies = self.parent_hit_enemies.clone();
-                    proj.parent_hit_enemies
-                        .append(&mut self.hit_enemies.clone());
-
-                    proj.pos = self.pos;
-                    proj.origin = self.origin;
-                    proj.direction = self.direction;
+        // go through the triggers in a fixed order, so the projectiles spawn in the same order
+        // every run
+        for trigger in enum_iterator::all::<OnHitTrigger>() {
+            let Some(items) = stats.on_hit_effects.get(&trigger) else {
+                continue;
+            };
+            let triggered = match trigger {
+                OnHitTrigger::Hit => true,
+                OnHitTrigger::Damage(ty) => self.damage(ty).is_some_and(|f| f > 0.0),
+                OnHitTrigger::Crit => crit,
+            };
+            for (proj, damage) in items {
+                if triggered {
+                    let mut proj = self.child(proj);
+                    proj.damage = Some(damage.clone());
                     proj.player_owned = true;
                     new_projectiles.push(proj);
                 }
@@ -85,13 +217,147 @@ impl Projectile {
         }
         new_projectiles
     }
+    /// Damage of a type it deals when it hits, before the player's damage modifiers
+    pub fn damage(&self, ty: DamageType) -> Option<f32> {
+        let damage = match &self.damage {
+            Some(damage) => damage,
+            None => &self.stats.as_ref()?.damage,
+        };
+        Some(damage.get(&ty)? * self.damage_scale)
+    }
+    /// A copy of a projectile fired from this one, which won't trigger on hit effects on the
+    /// enemies this already hit
+    fn child(&self, projectile: &Projectile) -> Projectile {
+        let mut child = projectile.clone();
+        child.stats = self.stats.clone();
+        child.damage = self.damage.clone();
+        child.damage_scale = self.damage_scale;
+        child.parent_hit_enemies = self.parent_hit_enemies.clone();
+        child
+            .parent_hit_enemies
+            .append(&mut self.hit_enemies.clone());
+        child.pos = self.pos;
+        child.origin = self.origin;
+        child.direction = self.direction;
+        child.player_owned = self.player_owned;
+        child
+    }
+    /// Turn towards the target, by at most the given angle
+    pub fn steer_towards(&mut self, target: Vec2, rate: f32) {
+        let angle = self.direction.angle_between(target - self.pos);
+        if angle.is_finite() {
+            self.direction = Vec2::from_angle(angle.clamp(-rate, rate))
+                .rotate(self.direction)
+                .normalize();
+        }
+    }
+    /// Turn an enemy's projectile against them, sent off in the direction of the player's
+    /// projectile that parried it, and dealing its damage
+    pub fn reflect(&mut self, parrier: &Projectile) {
+        self.player_owned = true;
+        self.direction = parrier.direction;
+        self.stats = parrier.stats.clone();
+        self.damage = parrier.damage.clone();
+        self.damage_scale = parrier.damage_scale;
+        self.origin = self.pos;
+        self.life = 0;
+        self.hit_enemies.clear();
+    }
+    /// Fraction of its speed the projectile loses every frame
+    pub fn drag(&self) -> f32 {
+        self.behaviors
+            .iter()
+            .map(|f| match f {
+                ProjectileBehavior::Drag(drag) => *drag,
+                _ => 0.0,
+            })
+            .sum()
+    }
+    /// If the projectile needs a target passed to [Projectile::tick]
+    pub fn seeks_target(&self) -> bool {
+        self.behaviors
+            .iter()
+            .any(|f| matches!(f, ProjectileBehavior::Homing(_)))
+    }
+    /// If the projectile hits the enemies it touches
+    pub fn hits_enemies(&self) -> bool {
+        !self
+            .behaviors
+            .iter()
+            .any(|f| matches!(f, ProjectileBehavior::Fuse))
+    }
+    /// Call once the projectile is fired, with the direction the one firing it is moving in
+    pub fn spawn(&mut self, move_vector: Vec2) {
+        let behaviors = std::mem::take(&mut self.behaviors);
+        for behavior in &behaviors {
+            behavior.spawn(self, move_vector);
+        }
+        self.behaviors = behaviors;
+    }
+    /// Call every frame before moving. Returns false if the projectile should break
+    pub fn tick(&mut self, target: Option<Vec2>, spawned: &mut Vec<Projectile>) -> bool {
+        let behaviors = std::mem::take(&mut self.behaviors);
+        let alive = behaviors
+            .iter()
+            .all(|behavior| behavior.tick(self, target, spawned));
+        self.behaviors = behaviors;
+        alive
+    }
+    /// Call after every chunk of movement in a frame. Returns false if the projectile should
+    /// break
+    pub fn moved(&mut self) -> bool {
+        let behaviors = std::mem::take(&mut self.behaviors);
+        let alive = behaviors.iter().all(|behavior| behavior.moved(self));
+        self.behaviors = behaviors;
+        alive
+    }
+    /// Call after hitting an enemy. Returns false if the projectile has no pierce left
+    pub fn hit_enemy(&mut self) -> bool {
+        let behaviors = std::mem::take(&mut self.behaviors);
+        for behavior in &behaviors {
+            behavior.hit_enemy(self);
+        }
+        self.behaviors = behaviors;
+        match &mut self.pierce {
+            Some(0) => false,
+            Some(pierce) => {
+                *pierce -= 1;
+                true
+            }
+            None => true,
+        }
+    }
+    /// Call after being pushed back inside the room from `unclamped`. Returns false if the
+    /// projectile breaks on the wall
+    pub fn hit_wall(&mut self, unclamped: Vec2) -> bool {
+        if self.bounces > 0 {
+            self.bounces -= 1;
+            if self.pos.x != unclamped.x {
+                self.direction.x = -self.direction.x;
+            }
+            if self.pos.y != unclamped.y {
+                self.direction.y = -self.direction.y;
+            }
+            return true;
+        }
+        let behaviors = std::mem::take(&mut self.behaviors);
+        let survived = behaviors.iter().any(|behavior| behavior.hit_wall(self));
+        self.behaviors = behaviors;
+        survived
+    }
+    /// Call once the projectile's lifetime runs out
+    pub fn expire(&self, spawned: &mut Vec<Projectile>) {
+        for behavior in &self.behaviors {
+            behavior.expire(self, spawned);
+        }
+    }
     pub fn draw(&self, assets: &Assets) {
         let x = self.pos.x.floor();
         let y = self.pos.y.floor();
         match &self.draw_type {
             DrawType::Sprite(sprite_x, sprite_y) => {
                 let params = DrawTextureParams {
-                    rotation: self.direction.to_angle(),
+                    rotation: self.direction.to_angle() + self.rotation,
                     ..Default::default()
                 };
                 assets
@@ -109,19 +375,59 @@ impl Projectile {
         }
     }
 }
+/// Returns the projectile data files refer to by the given name
+pub fn get_projectile(name: &str) -> Option<Projectile> {
+    Some(match name {
+        "acid puddle" => acid_puddle(),
+        "acid pool" => acid_pool(),
+        "slimeball" => slimeball(),
+        "slash" => slash(),
+        "dark slash" => dark_slash(),
+        "fireball" => fireball(),
+        "boomerang" => boomerang(),
+        "arrow" => arrow(),
+        "slow arrow" => slow_arrow(),
+        "hammer" => hammer(),
+        "boxing glove" => boxing_glove(),
+        "icicle" => icicle(),
+        "power orb" => power_orb(),
+        "blue power orb" => blue_power_orb(),
+        "light ray" => light_ray(),
+        "star explosion" => star_explosion(),
+        "star bazooka" => star_bazooka(),
+        "fire" => fire(),
+        "lance" => lance(),
+        "razor dart" => razor_dart(),
+        "seeker orb" => seeker_orb(),
+        "homing bolt" => homing_bolt(),
+        "bomb" => bomb(),
+        "wisp" => wisp(),
+        _ => return None,
+    })
+}
+
 pub const BASE_PROJECTILE: Projectile = Projectile {
     pos: Vec2::ZERO,
+    prev_pos: None,
     origin: Vec2::ZERO,
     direction: Vec2::ZERO,
     speed: 0.0,
-    drag: 0.0,
     draw_type: DrawType::Sprite(0.0, 0.0),
     life: 0,
     lifetime: 0,
-    boomerang: false,
     stats: None,
+    damage: None,
+    damage_scale: 1.0,
     player_owned: false,
     radius: 6.0,
+    knockback: 0.0,
+    pierce: None,
+    bounces: 0,
+    rotation: 0.0,
+    parries: false,
+    destructible: false,
+    reflectable: false,
+    behaviors: Vec::new(),
     hit_enemies: Vec::new(),
     parent_hit_enemies: Vec::new(),
 };
@@ -136,29 +442,41 @@ pub fn acid_puddle() -> Projectile {
     }
 }
 
+pub fn acid_pool() -> Projectile {
+    Projectile {
+        lifetime: 300,
+        ..acid_puddle()
+    }
+}
+
 pub fn slimeball() -> Projectile {
     Projectile {
         speed: 0.8,
         draw_type: DrawType::Sprite(10.0, 0.0),
         lifetime: 120,
+        destructible: true,
         ..BASE_PROJECTILE
     }
 }
 pub fn slash() -> Projectile {
     Projectile {
         speed: 5.0,
-        drag: 0.15,
+        behaviors: vec![ProjectileBehavior::Drag(0.15)],
         draw_type: DrawType::Sprite(0.0, 0.0),
         lifetime: 20,
+        knockback: 2.0,
+        parries: true,
         ..BASE_PROJECTILE
     }
 }
 pub fn dark_slash() -> Projectile {
     Projectile {
         speed: 8.0,
-        drag: 0.35,
+        behaviors: vec![ProjectileBehavior::Drag(0.35)],
         draw_type: DrawType::Sprite(9.0, 0.0),
         lifetime: 20,
+        knockback: 1.5,
+        parries: true,
         ..BASE_PROJECTILE
     }
 }
@@ -167,6 +485,7 @@ pub fn fireball() -> Projectile {
         speed: 2.0,
         draw_type: DrawType::Sprite(2.0, 0.0),
         lifetime: 140,
+        reflectable: true,
         ..BASE_PROJECTILE
     }
 }
@@ -175,7 +494,8 @@ pub fn boomerang() -> Projectile {
         speed: 3.0,
         draw_type: DrawType::Sprite(12.0, 0.0),
         lifetime: 140,
-        boomerang: true,
+        behaviors: vec![ProjectileBehavior::Boomerang],
+        knockback: 1.0,
         ..BASE_PROJECTILE
     }
 }
@@ -184,6 +504,7 @@ pub fn arrow() -> Projectile {
         speed: 7.0,
         draw_type: DrawType::Sprite(1.0, 0.0),
         lifetime: 80,
+        pierce: Some(0),
         ..BASE_PROJECTILE
     }
 }
@@ -192,6 +513,7 @@ pub fn slow_arrow() -> Projectile {
         speed: 3.0,
         draw_type: DrawType::Sprite(1.0, 0.0),
         lifetime: 160,
+        destructible: true,
         ..BASE_PROJECTILE
     }
 }
@@ -200,16 +522,17 @@ pub fn hammer() -> Projectile {
         speed: 3.0,
         draw_type: DrawType::Particle(particles::HAMMER),
         lifetime: 140,
-        boomerang: true,
+        behaviors: vec![ProjectileBehavior::Boomerang],
         ..BASE_PROJECTILE
     }
 }
 pub fn boxing_glove() -> Projectile {
     Projectile {
         speed: 4.0,
-        drag: 0.1,
+        behaviors: vec![ProjectileBehavior::Drag(0.1)],
         draw_type: DrawType::Sprite(3.0, 0.0),
         lifetime: 20,
+        knockback: 5.0,
         ..BASE_PROJECTILE
     }
 }
@@ -234,6 +557,7 @@ pub fn blue_power_orb() -> Projectile {
         speed: 1.5,
         lifetime: 90,
         draw_type: DrawType::Sprite(11.0, 0.0),
+        reflectable: true,
         ..BASE_PROJECTILE
     }
 }
@@ -251,6 +575,7 @@ pub fn star_explosion() -> Projectile {
         lifetime: 15,
         radius: 12.0,
         draw_type: DrawType::Particle(&particles::STAR_EXPLOSION),
+        knockback: 3.0,
         ..BASE_PROJECTILE
     }
 }
@@ -273,18 +598,67 @@ pub fn fire() -> Projectile {
 pub fn lance() -> Projectile {
     Projectile {
         speed: 12.0,
-        drag: 0.25,
+        behaviors: vec![ProjectileBehavior::Drag(0.25)],
         draw_type: DrawType::Sprite(112.0 / 16.0, 0.0),
         lifetime: 20,
+        knockback: 3.0,
+        parries: true,
         ..BASE_PROJECTILE
     }
 }
 pub fn razor_dart() -> Projectile {
     Projectile {
         speed: 6.0,
-        drag: 0.05,
+        behaviors: vec![ProjectileBehavior::Drag(0.05)],
         draw_type: DrawType::Sprite(128.0 / 16.0, 0.0),
         lifetime: 40,
         ..BASE_PROJECTILE
     }
 }
+pub fn seeker_orb() -> Projectile {
+    Projectile {
+        speed: 1.2,
+        lifetime: 180,
+        draw_type: DrawType::Sprite(11.0, 0.0),
+        behaviors: vec![ProjectileBehavior::Homing(0.03)],
+        reflectable: true,
+        ..BASE_PROJECTILE
+    }
+}
+pub fn homing_bolt() -> Projectile {
+    Projectile {
+        speed: 3.5,
+        lifetime: 90,
+        draw_type: DrawType::Sprite(5.0, 0.0),
+        behaviors: vec![ProjectileBehavior::Homing(0.08)],
+        pierce: Some(0),
+        ..BASE_PROJECTILE
+    }
+}
+pub fn bomb() -> Projectile {
+    Projectile {
+        speed: 3.5,
+        lifetime: 45,
+        radius: 4.0,
+        draw_type: DrawType::Sprite(14.0, 0.0),
+        behaviors: vec![
+            ProjectileBehavior::Drag(0.06),
+            ProjectileBehavior::Spin(0.3),
+            ProjectileBehavior::Stick,
+            ProjectileBehavior::Fuse,
+            ProjectileBehavior::SplitOnExpire(Box::new(star_explosion()), 1),
+        ],
+        ..BASE_PROJECTILE
+    }
+}
+pub fn wisp() -> Projectile {
+    Projectile {
+        speed: 1.5,
+        lifetime: 300,
+        radius: 4.0,
+        draw_type: DrawType::Sprite(15.0, 0.0),
+        behaviors: vec![ProjectileBehavior::Homing(0.1)],
+        pierce: Some(2),
+        ..BASE_PROJECTILE
+    }
+}
@@ -0,0 +1,287 @@
+use macroquad::prelude::*;
+
+use crate::{
+    player::{InventorySlot, movement_vector},
+    simulation::{InputFrame, InventoryAction},
+};
+
+const MAGIC: &[u8; 4] = b"RMBL";
+const VERSION: u8 = 2;
+
+const TAG_STEP: u8 = 0;
+const TAG_REPEAT: u8 = 1;
+const TAG_SWAP: u8 = 2;
+const TAG_DROP: u8 = 3;
+
+/// Positions are stored in eighths of a pixel
+const POSITION_SCALE: f32 = 8.0;
+
+#[derive(Clone, Copy)]
+pub enum ReplayEvent {
+    /// A single step of the simulation
+    Step(InputFrame),
+    /// An inventory change, made between steps
+    Inventory(InventoryAction),
+}
+
+/// Every input of a run, along with what's needed to start the run again.
+#[derive(Clone)]
+pub struct Replay {
+    pub seed: u64,
+    /// Index of the class picked on the main menu
+    pub class_index: u8,
+    /// Save the run was resumed from, if it didn't start from the beginning
+    pub save: Option<String>,
+    pub events: Vec<ReplayEvent>,
+}
+impl Replay {
+    pub fn new(seed: u64, class_index: u8) -> Self {
+        Self {
+            seed,
+            class_index,
+            save: None,
+            events: Vec::new(),
+        }
+    }
+    /// Record a step of the simulation. Returns the input as it will be played back,
+    /// which is what should be passed to the simulation so recording and playback match.
+    pub fn record_step(&mut self, input: &InputFrame) -> InputFrame {
+        let input = decode_step(encode_step(input));
+        self.events.push(ReplayEvent::Step(input));
+        input
+    }
+    /// Record an inventory action. Like [Replay::record_step], returns the action as it will be played back.
+    pub fn record_inventory(&mut self, action: &InventoryAction) -> InventoryAction {
+        let action = match action {
+            InventoryAction::Swap(slot) => InventoryAction::Swap(*slot),
+            InventoryAction::Drop(target) => {
+                InventoryAction::Drop(decode_position(encode_position(*target)))
+            }
+        };
+        self.events.push(ReplayEvent::Inventory(action));
+        action
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.push(self.class_index);
+        let save = self.save.as_deref().unwrap_or_default();
+        bytes.extend_from_slice(&(save.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(save.as_bytes());
+
+        // identical steps in a row are stored as a repeat count
+        let mut last_step = None;
+        let mut repeats: u8 = 0;
+        for event in self.events.iter() {
+            let step = match event {
+                ReplayEvent::Step(input) => Some(encode_step(input)),
+                ReplayEvent::Inventory(_) => None,
+            };
+            if step.is_some() && step == last_step && repeats < u8::MAX {
+                repeats += 1;
+                continue;
+            }
+            if repeats > 0 {
+                bytes.extend_from_slice(&[TAG_REPEAT, repeats]);
+                repeats = 0;
+            }
+            last_step = step;
+            match event {
+                ReplayEvent::Step(input) => {
+                    bytes.push(TAG_STEP);
+                    bytes.extend_from_slice(&encode_step(input));
+                }
+                ReplayEvent::Inventory(InventoryAction::Swap(slot)) => {
+                    bytes.push(TAG_SWAP);
+                    bytes.extend_from_slice(&encode_slot(*slot));
+                }
+                ReplayEvent::Inventory(InventoryAction::Drop(target)) => {
+                    bytes.push(TAG_DROP);
+                    bytes.extend_from_slice(&encode_position(*target));
+                }
+            }
+        }
+        if repeats > 0 {
+            bytes.extend_from_slice(&[TAG_REPEAT, repeats]);
+        }
+        bytes
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes, index: 0 };
+        if reader.take::<4>()? != *MAGIC {
+            return Err("not a replay file".into());
+        }
+        let [version] = reader.take()?;
+        if version != VERSION {
+            return Err(format!("unsupported replay version {version}"));
+        }
+        let seed = u64::from_le_bytes(reader.take()?);
+        let [class_index] = reader.take()?;
+        let save_len = u32::from_le_bytes(reader.take()?) as usize;
+        let save = reader.take_slice(save_len)?;
+
+        let mut replay = Replay::new(seed, class_index);
+        if save_len > 0 {
+            let save = String::from_utf8(save.to_vec()).map_err(|_| "invalid save")?;
+            replay.save = Some(save);
+        }
+        let mut last_step = None;
+        while reader.index < bytes.len() {
+            let [tag] = reader.take()?;
+            match tag {
+                TAG_STEP => {
+                    let step = decode_step(reader.take()?);
+                    last_step = Some(step);
+                    replay.events.push(ReplayEvent::Step(step));
+                }
+                TAG_REPEAT => {
+                    let [count] = reader.take()?;
+                    let Some(step) = last_step else {
+                        return Err("repeat before any step".into());
+                    };
+                    for _ in 0..count {
+                        replay.events.push(ReplayEvent::Step(step));
+                    }
+                }
+                TAG_SWAP => {
+                    let slot = decode_slot(reader.take()?)?;
+                    last_step = None;
+                    replay
+                        .events
+                        .push(ReplayEvent::Inventory(InventoryAction::Swap(slot)));
+                }
+                TAG_DROP => {
+                    let target = decode_position(reader.take()?);
+                    last_step = None;
+                    replay
+                        .events
+                        .push(ReplayEvent::Inventory(InventoryAction::Drop(target)));
+                }
+                _ => return Err(format!("unknown event tag {tag}")),
+            }
+        }
+        Ok(replay)
+    }
+}
+
+/// State of a replay being watched.
+pub struct Playback {
+    pub replay: Replay,
+    /// Index of the next event
+    index: usize,
+    pub paused: bool,
+    /// Steps played per frame
+    pub speed: u32,
+    /// Aim of the last step played
+    pub aim: Vec2,
+}
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            index: 0,
+            paused: false,
+            speed: 1,
+            aim: Vec2::ZERO,
+        }
+    }
+    pub fn is_done(&self) -> bool {
+        self.index >= self.replay.events.len()
+    }
+    /// Returns the events up to and including the next step
+    pub fn next_step(&mut self) -> &[ReplayEvent] {
+        let start = self.index;
+        while let Some(event) = self.replay.events.get(self.index) {
+            self.index += 1;
+            if let ReplayEvent::Step(input) = event {
+                self.aim = input.aim;
+                break;
+            }
+        }
+        &self.replay.events[start..self.index]
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    index: usize,
+}
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take_slice(N)?.try_into().unwrap())
+    }
+    fn take_slice(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .bytes
+            .get(self.index..self.index + len)
+            .ok_or("unexpected end of replay")?;
+        self.index += len;
+        Ok(bytes)
+    }
+}
+
+fn encode_position(pos: Vec2) -> [u8; 4] {
+    let x = (pos.x * POSITION_SCALE).round() as i16;
+    let y = (pos.y * POSITION_SCALE).round() as i16;
+    let [x0, x1] = x.to_le_bytes();
+    let [y0, y1] = y.to_le_bytes();
+    [x0, x1, y0, y1]
+}
+fn decode_position(bytes: [u8; 4]) -> Vec2 {
+    let x = i16::from_le_bytes([bytes[0], bytes[1]]);
+    let y = i16::from_le_bytes([bytes[2], bytes[3]]);
+    Vec2::new(x as f32, y as f32) / POSITION_SCALE
+}
+
+fn encode_step(input: &InputFrame) -> [u8; 5] {
+    let flags = [
+        input.move_vector.x < 0.0,
+        input.move_vector.x > 0.0,
+        input.move_vector.y < 0.0,
+        input.move_vector.y > 0.0,
+        input.attack,
+        input.roll,
+        input.interact,
+        input.ability,
+    ]
+    .into_iter()
+    .enumerate()
+    .fold(0, |flags, (bit, set)| flags | ((set as u8) << bit));
+    let [x0, x1, y0, y1] = encode_position(input.aim);
+    [flags, x0, x1, y0, y1]
+}
+fn decode_step(bytes: [u8; 5]) -> InputFrame {
+    let flag = |bit: u8| bytes[0] & (1 << bit) != 0;
+    InputFrame {
+        move_vector: movement_vector(flag(0), flag(1), flag(2), flag(3)),
+        aim: decode_position([bytes[1], bytes[2], bytes[3], bytes[4]]),
+        attack: flag(4),
+        roll: flag(5),
+        interact: flag(6),
+        ability: flag(7),
+    }
+}
+
+fn encode_slot(slot: InventorySlot) -> [u8; 2] {
+    match slot {
+        InventorySlot::Helmet => [0, 0],
+        InventorySlot::Chestplate => [1, 0],
+        InventorySlot::Hand => [2, 0],
+        InventorySlot::Talisman(index) => [3, index as u8],
+        InventorySlot::Inventory(index) => [4, index as u8],
+        InventorySlot::Active => [5, 0],
+    }
+}
+fn decode_slot(bytes: [u8; 2]) -> Result<InventorySlot, String> {
+    Ok(match bytes[0] {
+        0 => InventorySlot::Helmet,
+        1 => InventorySlot::Chestplate,
+        2 => InventorySlot::Hand,
+        3 => InventorySlot::Talisman(bytes[1] as usize),
+        4 => InventorySlot::Inventory(bytes[1] as usize),
+        5 => InventorySlot::Active,
+        kind => return Err(format!("unknown inventory slot {kind}")),
+    })
+}
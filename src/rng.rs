@@ -0,0 +1,100 @@
+/// Seedable random number generator. Uses the same PCG algorithm as macroquad's global generator,
+/// but each instance owns its state so multiple streams can exist side by side.
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+impl Rng {
+    const MULTIPLIER: u64 = 6364136223846793005;
+    const INCREMENT: u64 = 1442695040888963407;
+
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.rand();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.rand();
+        rng
+    }
+    /// Restore a generator from [Rng::state]
+    pub fn from_state(state: u64) -> Self {
+        Self { state }
+    }
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+    /// Returns a random number in the range of 0 to [u32::MAX]
+    pub fn rand(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(Self::INCREMENT);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+    /// Returns a random number in the range of low to high (exclusive)
+    pub fn gen_range<T: RandomRange>(&mut self, low: T, high: T) -> T {
+        T::gen_range(self, low, high)
+    }
+    /// Creates a new generator seeded from this one
+    pub fn split(&mut self) -> Rng {
+        Rng::new(((self.rand() as u64) << 32) | self.rand() as u64)
+    }
+}
+
+pub trait RandomRange {
+    fn gen_range(rng: &mut Rng, low: Self, high: Self) -> Self;
+}
+macro_rules! impl_random_range {
+    ($($ty:ty),*) => {
+        $(
+            impl RandomRange for $ty {
+                fn gen_range(rng: &mut Rng, low: Self, high: Self) -> Self {
+                    let r = rng.rand() as f64 / (u32::MAX as f64 + 1.0);
+                    (low as f64 + (high as f64 - low as f64) * r) as Self
+                }
+            }
+        )*
+    };
+}
+impl_random_range!(f32, u8, u16, u32, u64, usize, i32);
+
+/// The random number streams of a single run.
+///
+/// Each part of the game draws from its own stream, so that for example how enemies move
+/// can't change which rooms are generated or what the shop offers.
+#[derive(Clone)]
+pub struct RunRng {
+    /// Room layouts, enemy types and curse hazards
+    pub layouts: Rng,
+    /// Item rewards and enchantments
+    pub loot: Rng,
+    /// Chaos curses given by the shop
+    pub curses: Rng,
+    /// Enemy behaviour and anything else affected by how the player fights
+    pub ai: Rng,
+}
+impl RunRng {
+    pub fn new(seed: u64) -> Self {
+        let mut root = Rng::new(seed);
+        Self {
+            layouts: root.split(),
+            loot: root.split(),
+            curses: root.split(),
+            ai: root.split(),
+        }
+    }
+    /// Restore the streams from [RunRng::states]
+    pub fn from_states(states: [u64; 4]) -> Self {
+        let [layouts, loot, curses, ai] = states.map(Rng::from_state);
+        Self {
+            layouts,
+            loot,
+            curses,
+            ai,
+        }
+    }
+    pub fn states(&self) -> [u64; 4] {
+        [&self.layouts, &self.loot, &self.curses, &self.ai].map(Rng::state)
+    }
+}
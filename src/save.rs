@@ -0,0 +1,337 @@
+use std::fmt::Write;
+
+use macroquad::prelude::*;
+
+use crate::{
+    items::*,
+    player::{ChaosCurse, InventorySlot, Player},
+    projectiles::DamageType,
+    rng::RunRng,
+    simulation::{GameState, Simulation},
+    utils::*,
+};
+
+const HEADER: &str = "ramble save 1";
+
+/// Serialize an item as its id, followed by how it differs from its definition.
+///
+/// Damage is stored as a multiplier of the item's base damage, and items and enchantments are
+/// referred to by id, so saves keep working when items are rebalanced.
+fn write_item(out: &mut String, item: &Item, items: &[Item]) {
+    write!(out, " {}", item.id).unwrap();
+    if let Some(enchantment) = &item.enchantment {
+        write!(out, " e{}", enchantment.id).unwrap();
+    }
+    if item.internal_stats.max_lives > 0 {
+        write!(out, " l{}", item.internal_stats.lives).unwrap();
+    }
+    if let Some(base) = items.iter().find(|f| f.id == item.id) {
+        for (ty, amt) in item.internal_stats.damage.iter() {
+            if let Some(base_amt) = base.internal_stats.damage.get(ty)
+                && *base_amt != 0.0
+            {
+                write!(out, " d{}={}", ty.to_text(), amt / base_amt).unwrap();
+            }
+        }
+    }
+}
+
+fn read_item<'a>(
+    mut tokens: impl Iterator<Item = &'a str>,
+    items: &[Item],
+    enchantments: &[Enchantment],
+) -> Result<Item, String> {
+    let id: u16 = parse(tokens.next())?;
+    let mut item = if id == GIFT_ID {
+        gift()
+    } else {
+        items
+            .iter()
+            .find(|f| f.id == id)
+            .ok_or(format!("unknown item id {id}"))?
+            .clone()
+    };
+    let base_damage = item.internal_stats.damage.clone();
+    for token in tokens {
+        if let Some(id) = token.strip_prefix('e') {
+            let id: u16 = parse(Some(id))?;
+            let enchantment = enchantments
+                .iter()
+                .find(|f| f.id == id)
+                .ok_or(format!("unknown enchantment id {id}"))?;
+            item.enchantment = Some(enchantment.clone());
+        } else if let Some(lives) = token.strip_prefix('l') {
+            let lives: u16 = parse(Some(lives))?;
+            item.internal_stats.lives = lives.min(item.internal_stats.max_lives);
+        } else if let Some(damage) = token.strip_prefix('d') {
+            let (ty, multiplier) = damage
+                .split_once('=')
+                .ok_or(format!("invalid damage '{damage}'"))?;
+            let ty = DamageType::from_text(ty).ok_or(format!("unknown damage type '{ty}'"))?;
+            let multiplier: f32 = parse(Some(multiplier))?;
+            // damage types the item no longer deals are ignored
+            if let Some(base) = base_damage.get(&ty) {
+                item.internal_stats.damage.insert(ty, base * multiplier);
+            }
+        } else {
+            return Err(format!("unexpected '{token}'"));
+        }
+    }
+    Ok(item)
+}
+
+fn parse<T: std::str::FromStr>(token: Option<&str>) -> Result<T, String> {
+    let token = token.ok_or("unexpected end of line")?;
+    token
+        .parse()
+        .map_err(|_| format!("invalid value '{token}'"))
+}
+
+impl<'a> Simulation<'a> {
+    /// Serialize the run. Only possible between rooms, returns None otherwise.
+    pub fn to_save(&self) -> Option<String> {
+        let GameState::PostRound(_, offers) = &self.state else {
+            return None;
+        };
+        let mut out = String::from(HEADER);
+        out.push('\n');
+        let [layouts, loot, curses, ai] = self.rng.states();
+        writeln!(out, "seed {}", self.seed).unwrap();
+        writeln!(out, "rng {layouts} {loot} {curses} {ai}").unwrap();
+        let dungeon = &self.dungeon_manager;
+        writeln!(
+            out,
+            "dungeon {} {} {}",
+            dungeon.world_index, dungeon.room_index, dungeon.total_room_index
+        )
+        .unwrap();
+
+        let player = &self.player;
+        let stats = player.internal_stats();
+        writeln!(out, "lives {} {}", stats.lives, stats.max_lives).unwrap();
+        if player.active_counter > 0.0 {
+            writeln!(out, "player active_counter {}", player.active_counter).unwrap();
+        }
+        for (ty, amt) in stats.damage_modifiers.iter() {
+            writeln!(out, "damage_modifier {} {amt}", ty.to_text()).unwrap();
+        }
+        for curse in player.curses() {
+            writeln!(out, "curse {curse:?}").unwrap();
+        }
+
+        let mut slots: Vec<(String, Option<&Item>)> = vec![
+            ("helmet".into(), player.slot(InventorySlot::Helmet)),
+            ("chestplate".into(), player.slot(InventorySlot::Chestplate)),
+            ("hand".into(), player.slot(InventorySlot::Hand)),
+            ("active".into(), player.slot(InventorySlot::Active)),
+            ("cursor".into(), player.cursor_item()),
+        ];
+        for (index, item) in player.talismans().iter().enumerate() {
+            slots.push((format!("talisman {index}"), item.as_ref()));
+        }
+        for (index, item) in player.inventory().iter().enumerate() {
+            slots.push((format!("inventory {index}"), item.as_ref()));
+        }
+        for (slot, item) in slots {
+            if let Some(item) = item {
+                out.push_str(&slot);
+                write_item(&mut out, item, self.items);
+                out.push('\n');
+            }
+        }
+        if let Some(offers) = offers {
+            for item in offers.iter() {
+                out.push_str("offer");
+                write_item(&mut out, item, self.items);
+                out.push('\n');
+            }
+        }
+        for (pos, item) in self.dropped_items.iter() {
+            write!(out, "dropped {} {}", pos.x, pos.y).unwrap();
+            write_item(&mut out, item, self.items);
+            out.push('\n');
+        }
+        Some(out)
+    }
+    /// Resume a run from [Simulation::to_save]
+    pub fn from_save(
+        items: &'a [Item],
+        enchantments: &'a [Enchantment],
+        text: &str,
+    ) -> Result<Self, String> {
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err("not a save file".into());
+        }
+        let mut player = Player::new(Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0));
+        let mut sim = Simulation::new(items, enchantments, Player::default(), 0);
+        let mut offers = Vec::new();
+
+        for (index, line) in lines.enumerate() {
+            let mut tokens = line.split_whitespace();
+            let Some(key) = tokens.next() else {
+                continue;
+            };
+            // line numbers start after the header
+            read_line(key, tokens, &mut sim, &mut player, &mut offers)
+                .map_err(|err| format!("line {}: {err}", index + 2))?;
+        }
+
+        sim.player = player;
+        sim.state = match <[Item; 3]>::try_from(offers) {
+            Ok(offers) => GameState::PostRound(40, Some(Box::new(offers))),
+            Err(offers) if offers.is_empty() => GameState::PostRound(100, None),
+            Err(_) => return Err("expected 3 shop offers".into()),
+        };
+        sim.rebuild_item_grid();
+        Ok(sim)
+    }
+}
+
+/// Read a line of a save, after its key, in to the run being resumed
+fn read_line<'a>(
+    key: &str,
+    mut tokens: impl Iterator<Item = &'a str>,
+    sim: &mut Simulation,
+    player: &mut Player,
+    offers: &mut Vec<Item>,
+) -> Result<(), String> {
+    let (items, enchantments) = (sim.items, sim.enchantments);
+    match key {
+        "seed" => sim.seed = parse(tokens.next())?,
+        "rng" => {
+            let mut states = [0; 4];
+            for state in states.iter_mut() {
+                *state = parse(tokens.next())?;
+            }
+            sim.rng = RunRng::from_states(states);
+        }
+        "dungeon" => {
+            let dungeon = &mut sim.dungeon_manager;
+            dungeon.world_index = parse(tokens.next())?;
+            dungeon.room_index = parse(tokens.next())?;
+            dungeon.total_room_index = parse(tokens.next())?;
+            if dungeon.world_index >= dungeon.worlds.len() {
+                return Err(format!("unknown world {}", dungeon.world_index));
+            }
+        }
+        "lives" => {
+            let stats = player.internal_stats_mut();
+            stats.lives = parse(tokens.next())?;
+            stats.max_lives = parse(tokens.next())?;
+        }
+        "player" => match tokens.next().unwrap_or_default() {
+            "active_counter" => player.active_counter = parse(tokens.next())?,
+            field => return Err(format!("unknown player field '{field}'")),
+        },
+        "damage_modifier" => {
+            let ty = tokens.next().unwrap_or_default();
+            let ty = DamageType::from_text(ty).ok_or(format!("unknown damage type '{ty}'"))?;
+            player
+                .internal_stats_mut()
+                .damage_modifiers
+                .insert(ty, parse(tokens.next())?);
+        }
+        "curse" => {
+            let name = tokens.next().unwrap_or_default();
+            let curse = enum_iterator::all::<ChaosCurse>()
+                .find(|f| format!("{f:?}") == name)
+                .ok_or(format!("unknown curse '{name}'"))?;
+            player.add_curse(curse);
+        }
+        "cursor" => {
+            player.set_cursor_item(Some(read_item(tokens, items, enchantments)?));
+        }
+        "helmet" | "chestplate" | "hand" | "active" | "talisman" | "inventory" => {
+            let slot = match key {
+                "helmet" => InventorySlot::Helmet,
+                "chestplate" => InventorySlot::Chestplate,
+                "hand" => InventorySlot::Hand,
+                "active" => InventorySlot::Active,
+                "talisman" => InventorySlot::Talisman(parse(tokens.next())?),
+                _ => InventorySlot::Inventory(parse(tokens.next())?),
+            };
+            let target = player
+                .slot_mut(slot)
+                .ok_or(format!("slot {slot:?} out of range"))?;
+            *target = Some(read_item(tokens, items, enchantments)?);
+        }
+        "offer" => offers.push(read_item(tokens, items, enchantments)?),
+        "dropped" => {
+            let pos = Vec2::new(parse(tokens.next())?, parse(tokens.next())?);
+            let item = read_item(tokens, items, enchantments)?;
+            sim.dropped_items.push((pos, item));
+        }
+        _ => return Err(format!("unknown key '{key}'")),
+    }
+    Ok(())
+}
+
+const SAVE_KEY: &str = "ramble.save";
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_save(text: &str) {
+    if let Err(err) = std::fs::write(SAVE_KEY, text) {
+        eprintln!("failed to write save: {err}");
+    }
+}
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_save() -> Option<String> {
+    std::fs::read_to_string(SAVE_KEY).ok()
+}
+#[cfg(not(target_arch = "wasm32"))]
+pub fn delete_save() {
+    let _ = std::fs::remove_file(SAVE_KEY);
+}
+
+/// Bindings to the `ramble_storage` plugin in `web/gl.js`, which stores values in localStorage
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    unsafe extern "C" {
+        pub fn ramble_storage_set(key: *const u8, key_len: u32, value: *const u8, value_len: u32);
+        /// Returns length of the stored value, or -1 if there is none
+        pub fn ramble_storage_len(key: *const u8, key_len: u32) -> i32;
+        pub fn ramble_storage_get(key: *const u8, key_len: u32, buffer: *mut u8, buffer_len: u32);
+        pub fn ramble_storage_remove(key: *const u8, key_len: u32);
+    }
+
+    #[unsafe(no_mangle)]
+    pub extern "C" fn ramble_storage_crate_version() -> u32 {
+        1
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write_save(text: &str) {
+    unsafe {
+        storage::ramble_storage_set(
+            SAVE_KEY.as_ptr(),
+            SAVE_KEY.len() as u32,
+            text.as_ptr(),
+            text.len() as u32,
+        );
+    }
+}
+#[cfg(target_arch = "wasm32")]
+pub fn read_save() -> Option<String> {
+    let len = unsafe { storage::ramble_storage_len(SAVE_KEY.as_ptr(), SAVE_KEY.len() as u32) };
+    if len < 0 {
+        return None;
+    }
+    let mut buffer = vec![0; len as usize];
+    unsafe {
+        storage::ramble_storage_get(
+            SAVE_KEY.as_ptr(),
+            SAVE_KEY.len() as u32,
+            buffer.as_mut_ptr(),
+            buffer.len() as u32,
+        );
+    }
+    String::from_utf8(buffer).ok()
+}
+#[cfg(target_arch = "wasm32")]
+pub fn delete_save() {
+    unsafe {
+        storage::ramble_storage_remove(SAVE_KEY.as_ptr(), SAVE_KEY.len() as u32);
+    }
+}
@@ -0,0 +1,1036 @@
+use std::{collections::HashMap, f32::consts::PI};
+
+use hashmap_macro::hashmap;
+use macroquad::prelude::*;
+
+use crate::{
+    dungeon::*, enemy::*, items::*, player::*, projectiles::*, rng::RunRng, spatial::SpatialGrid,
+    utils::*, worlds::*,
+};
+
+pub enum GameState {
+    RoundActive,
+    PostRound(u32, Option<Box<[Item; 3]>>),
+    PreRound(u32),
+    /// Bool is if game over is because of win. False if loss
+    GameOver(u32, bool),
+}
+impl GameState {
+    pub fn should_draw(&self) -> bool {
+        match self {
+            GameState::GameOver(_, _) | GameState::RoundActive | GameState::PostRound(_, _) => true,
+            GameState::PreRound(frame) => *frame >= 40 / 2,
+        }
+    }
+    pub fn door_frame(&self) -> f32 {
+        match self {
+            GameState::GameOver(_, _) => 0.0,
+            GameState::RoundActive => 0.0,
+            GameState::PostRound(frame, _) => (*frame as f32 / 10.0 * 4.0).floor().min(3.0) * 2.0,
+            GameState::PreRound(frame) => {
+                if *frame >= PREROUND_TRANSITION_TIME / 2 {
+                    0.0
+                } else {
+                    6.0
+                }
+            }
+        }
+    }
+    /// Vertical camera offset used for the room transition shake
+    pub fn camera_offset(&self) -> f32 {
+        let GameState::PreRound(frame) = self else {
+            return 0.0;
+        };
+        if *frame < PREROUND_TRANSITION_TIME / 2 {
+            let amt = *frame as f32 / (PREROUND_TRANSITION_TIME as f32 / 2.0);
+            amt * 2.0
+        } else if *frame <= PREROUND_TRANSITION_TIME {
+            let amt = (*frame - PREROUND_TRANSITION_TIME / 2) as f32
+                / (PREROUND_TRANSITION_TIME as f32 / 2.0);
+            amt * 2.0 - 2.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The player's input for a single frame of the simulation.
+#[derive(Clone, Copy, Default)]
+pub struct InputFrame {
+    /// Normalized movement direction
+    pub move_vector: Vec2,
+    /// Point in world space the player is aiming at
+    pub aim: Vec2,
+    pub attack: bool,
+    pub roll: bool,
+    /// Use the active item
+    pub ability: bool,
+    /// Pick up items and buy from the shop. Should only be true on the frame it was pressed.
+    pub interact: bool,
+}
+
+/// A change the player made to their inventory while it was open.
+#[derive(Clone, Copy)]
+pub enum InventoryAction {
+    /// Swap the cursor item with the item in a slot
+    Swap(InventorySlot),
+    /// Drop the cursor item in the direction of a point
+    Drop(Vec2),
+}
+
+/// How a hit landed, which changes how its damage number is drawn
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HitKind {
+    Normal,
+    Crit,
+    /// Taken by the enemy's shield
+    Shield,
+    /// Resisted completely by the enemy
+    Immune,
+}
+
+/// Damage of a single type dealt to an enemy by a hit
+#[derive(Clone, Copy)]
+pub struct Hit {
+    pub enemy_id: usize,
+    /// Where the enemy was when it was hit
+    pub pos: Vec2,
+    pub ty: DamageType,
+    pub amount: f32,
+    pub kind: HitKind,
+}
+impl Hit {
+    /// Damage an enemy took from something other than a projectile, like a status effect or
+    /// hitting a wall
+    fn dealt(enemy: &Enemy, ty: DamageType, amount: f32) -> Self {
+        Self {
+            enemy_id: enemy.id,
+            pos: enemy.pos,
+            ty,
+            amount,
+            kind: if amount <= 0.0 {
+                HitKind::Immune
+            } else {
+                HitKind::Normal
+            },
+        }
+    }
+}
+
+/// Vertical position of the item shop, given the frame of [GameState::PostRound]
+pub fn shop_y(frame: u32) -> f32 {
+    let y = 88.0;
+    if frame > 30 {
+        y + 14.0 - (frame.min(40) - 30) as f32 / 10.0 * 14.0
+    } else {
+        y + 14.0
+    }
+}
+
+/// Game logic for a single run, independent of rendering and input handling.
+pub struct Simulation<'a> {
+    pub items: &'a [Item],
+    pub enchantments: &'a [Enchantment],
+    pub player: Player,
+    pub state: GameState,
+    pub enemies: Vec<Enemy>,
+    pub dropped_items: Vec<(Vec2, Item)>,
+    enemy_id: usize,
+    pub projectiles: Vec<Projectile>,
+    pub dungeon_manager: DungeonManager,
+    /// Seed the run was started with
+    pub seed: u64,
+    pub rng: RunRng,
+    /// Total damage the player has dealt to enemies this run, by type
+    pub damage_dealt: HashMap<DamageType, f32>,
+    /// Hits on enemies during the last step, for showing damage numbers
+    pub hits: Vec<Hit>,
+    /// Enemies by position, rebuilt every step before projectiles move
+    enemy_grid: SpatialGrid,
+    /// Dropped items by position, rebuilt every step and when the player drops an item
+    item_grid: SpatialGrid,
+}
+impl<'a> Simulation<'a> {
+    pub fn new(
+        items: &'a [Item],
+        enchantments: &'a [Enchantment],
+        player: Player,
+        seed: u64,
+    ) -> Self {
+        Self {
+            items,
+            enchantments,
+            state: GameState::PostRound(100, None),
+            player,
+            enemies: Vec::new(),
+            dropped_items: Vec::new(),
+            enemy_id: 0,
+            projectiles: Vec::new(),
+            dungeon_manager: DungeonManager::new(worlds().iter().collect()),
+            seed,
+            rng: RunRng::new(seed),
+            damage_dealt: HashMap::new(),
+            hits: Vec::new(),
+            enemy_grid: SpatialGrid::default(),
+            item_grid: SpatialGrid::default(),
+        }
+    }
+    /// Returns whether the run is over and its end transition has finished.
+    /// Value is true if the run was won.
+    pub fn finished(&self) -> Option<bool> {
+        match self.state {
+            GameState::GameOver(frames, win) if frames > 30 => Some(win),
+            _ => None,
+        }
+    }
+    pub fn get_item_reward(&mut self) -> Item {
+        let rng = &mut self.rng.loot;
+        let mut item = select_random(rng, self.items).clone();
+        let room_modifier_index = self.dungeon_manager.total_room_index / 2;
+        // roll the damage types in a fixed order, so the same seed always rolls the same item
+        for ty in enum_iterator::all::<DamageType>() {
+            if let Some(amt) = item.internal_stats.damage.get_mut(&ty) {
+                *amt *= rng.gen_range(1.0, 1.25 + room_modifier_index as f32);
+            }
+        }
+
+        if let ItemType::Held(_) = item.ty
+            && rng.gen_range(0, 100) <= ENCHANT_CHANCE
+        {
+            let enchant = select_random(rng, self.enchantments).clone();
+            item.enchantment = Some(enchant);
+        }
+        item
+    }
+    pub fn give_curse(&mut self, curse: ChaosCurse) {
+        let mut lost_items = Vec::new();
+        match &curse {
+            ChaosCurse::RefillHealth => {
+                self.player.regen();
+            }
+            ChaosCurse::RepairArmor => {
+                self.player.repair_armor();
+            }
+            ChaosCurse::DoubledUnholyDmg => {
+                self.player.internal_stats_mut().merge(&Stats {
+                    damage_modifiers: hashmap!(DamageType::Unholy=>1.0),
+                    ..Default::default()
+                });
+            }
+            ChaosCurse::HalvedHolyDmg => {
+                self.player.internal_stats_mut().merge(&Stats {
+                    damage_modifiers: hashmap!(DamageType::Holy=>-0.5),
+                    ..Default::default()
+                });
+            }
+            ChaosCurse::Gift => {
+                let pos = Vec2::new(SCREEN_WIDTH / 2.0, 64.0);
+                self.dropped_items.push((pos, gift()));
+            }
+            _ => {}
+        }
+        self.player.add_curse(curse);
+        let inventory_curse_count = self
+            .player
+            .curses()
+            .iter()
+            .filter(|f| matches!(*f, ChaosCurse::LessInventory))
+            .count();
+
+        for index in 0..inventory_curse_count * 2 {
+            if let Some(slot) = self.player.slot_mut(InventorySlot::Inventory(index))
+                && let Some(item) = slot.take()
+            {
+                lost_items.push(item);
+            }
+        }
+        for item in lost_items.into_iter() {
+            if self.player.inv_slot_free(&item.ty) {
+                self.player.give_item(item);
+            } else {
+                let pos =
+                    self.player.pos + Vec2::from_angle(self.rng.ai.gen_range(0.0, PI * 2.0)) * 5.0;
+                self.dropped_items.push((pos, item));
+            }
+        }
+    }
+    fn spawn_enemies(&mut self, buffer: &mut Vec<Enemy>) {
+        for mut enemy in buffer.drain(..) {
+            enemy.pos.y += 32.0;
+            self.add_enemy(enemy);
+        }
+    }
+    /// Add an enemy to the room with a new id
+    fn add_enemy(&mut self, mut enemy: Enemy) {
+        let shield_count = self
+            .player
+            .curses()
+            .iter()
+            .filter(|f| matches!(*f, ChaosCurse::EnemyShields))
+            .count();
+        enemy.id = self.enemy_id;
+        enemy.shield = shield_count as f32 * 5.0;
+        // appears where it's added, instead of sliding there from where it was created
+        enemy.prev_pos = enemy.pos;
+        self.enemy_id += 1;
+        self.enemies.push(enemy);
+    }
+    /// Apply the death effects of an enemy that died at a position
+    fn on_death(&mut self, ty: &'static EnemyType, pos: Vec2) {
+        let top_left_corner = Vec2::new(4.0, 28.0);
+        let bottom_right_corner = Vec2::new(SCREEN_WIDTH - 4.0, SCREEN_HEIGHT - 8.0);
+        for effect in ty.on_death.iter() {
+            match effect {
+                DeathEffect::Split(split, count) => {
+                    for i in 0..*count {
+                        let offset = Vec2::from_angle(i as f32 * (PI * 2.0) / *count as f32) * 6.0;
+                        let pos = (pos + offset).clamp(top_left_corner, bottom_right_corner);
+                        let enemy = Enemy::new(split, pos, 0, &mut self.rng.ai);
+                        self.add_enemy(enemy);
+                    }
+                }
+                DeathEffect::Explode(projectile, count) => {
+                    for i in 0..*count {
+                        let mut projectile = projectile.clone();
+                        projectile.pos = pos;
+                        projectile.direction =
+                            Vec2::from_angle(i as f32 * (PI * 2.0) / *count as f32);
+                        projectile.player_owned = false;
+                        projectile.origin = projectile.pos;
+                        projectile.spawn(Vec2::ZERO);
+                        self.projectiles.push(projectile);
+                    }
+                }
+                DeathEffect::Hazard(projectile) => {
+                    let mut projectile = projectile.clone();
+                    projectile.pos = pos;
+                    projectile.player_owned = false;
+                    projectile.origin = projectile.pos;
+                    projectile.spawn(Vec2::ZERO);
+                    self.projectiles.push(projectile);
+                }
+                DeathEffect::Heal(radius, amount) => {
+                    for enemy in self.enemies.iter_mut() {
+                        if enemy.pos.distance(pos) <= *radius {
+                            enemy.health = (enemy.health + amount).min(enemy.ty.max_health);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    pub fn apply_inventory_action(&mut self, action: &InventoryAction) {
+        match action {
+            InventoryAction::Swap(slot) => self.player.swap_cursor_item(*slot),
+            InventoryAction::Drop(target) => {
+                if let Some(item) = self.player.set_cursor_item(None) {
+                    let mut pos = self.player.pos;
+                    pos += (*target - self.player.pos).normalize() * 3.0;
+                    self.dropped_items.push((pos, item));
+                    self.rebuild_item_grid();
+                }
+            }
+        }
+    }
+    /// Call after changing the dropped items, so [Simulation::item_under_player] finds them
+    pub(crate) fn rebuild_item_grid(&mut self) {
+        self.item_grid
+            .rebuild(self.dropped_items.iter().map(|(pos, _)| *pos));
+    }
+    /// Returns index of the closest dropped item within pick up range of the player
+    pub fn item_under_player(&self) -> Option<usize> {
+        let mut item_under_player: Option<(usize, f32)> = None;
+        let mut nearby = Vec::new();
+        self.item_grid.query(self.player.pos, 7.0, &mut nearby);
+        for index in nearby {
+            let Some((pos, _)) = self.dropped_items.get(index) else {
+                continue;
+            };
+            let dist = pos.distance(self.player.pos);
+            if dist <= 7.0 && item_under_player.is_none_or(|f| f.1 > dist) {
+                item_under_player = Some((index, dist));
+            }
+        }
+        item_under_player.map(|f| f.0)
+    }
+    /// Returns index of the shop item the player is standing at, if the shop is open
+    pub fn shop_item_in_range(&self) -> Option<usize> {
+        let GameState::PostRound(frame, Some(_)) = &self.state else {
+            return None;
+        };
+        if !(31..=40).contains(frame) {
+            return None;
+        }
+        let y = shop_y(*frame);
+        (0..3).find(|index| {
+            let x = SCREEN_WIDTH / 2.0 - 32.0 + 16.0 * *index as f32;
+            // check for distance with a bias against X coordinate
+            (self.player.pos.x - x).powi(2) * 3.0 + (self.player.pos.y - y).powi(2)
+                <= 18.0_f32.powi(2)
+        })
+    }
+    fn interact(&mut self) {
+        if let Some(index) = self.shop_item_in_range()
+            && let GameState::PostRound(frame, items) = &mut self.state
+        {
+            let Some(offers) = items else {
+                return;
+            };
+            if self.player.inv_slot_free(&offers[index].ty) {
+                let o = items.take().unwrap();
+                self.player.give_item(o.into_iter().nth(index).unwrap());
+                *frame = 41;
+                let all = enum_iterator::all::<ChaosCurse>().collect::<Vec<_>>();
+                let curse = *select_random(&mut self.rng.curses, &all);
+                self.give_curse(curse);
+            }
+            return;
+        }
+        if let Some(index) = self.item_under_player()
+            && self.player.inv_slot_free(&self.dropped_items[index].1.ty)
+        {
+            let item = self.dropped_items.remove(index).1;
+            if item.id == GIFT_ID {
+                let reward = self.get_item_reward();
+                self.player.give_item(reward);
+            } else {
+                self.player.give_item(item);
+            }
+        }
+    }
+    /// Advance the simulation by a single frame.
+    pub fn step(&mut self, input: &InputFrame) {
+        self.hits.clear();
+        self.player.prev_pos = self.player.pos;
+        for enemy in self.enemies.iter_mut() {
+            enemy.prev_pos = enemy.pos;
+        }
+        for projectile in self.projectiles.iter_mut() {
+            projectile.prev_pos = Some(projectile.pos);
+        }
+        match &mut self.state {
+            GameState::GameOver(frames, _) => {
+                *frames += 1;
+            }
+            GameState::RoundActive => {
+                self.update(input);
+            }
+            GameState::PostRound(frame, _) => {
+                if *frame != 40 {
+                    *frame = frame.saturating_add(1);
+                }
+                self.update(input);
+            }
+            GameState::PreRound(frame) => {
+                let max = PREROUND_TRANSITION_TIME + PREROUND_GRACE_TIME;
+                *frame += 1;
+                if *frame > max {
+                    self.state = GameState::RoundActive;
+                }
+            }
+        }
+        self.rebuild_item_grid();
+    }
+    /// Fire the held weapon's projectiles towards the aim, fanned out by the player's spread,
+    /// and made stronger by the fraction of full charge the attack was charged to.
+    /// Returns false if the player isn't holding a weapon
+    fn fire_held(&mut self, aim: Vec2, move_vector: Vec2, charge: f32) -> bool {
+        let Some(held) = self.player.slot(InventorySlot::Hand) else {
+            return false;
+        };
+        let ItemType::Held(held) = &held.ty else {
+            return false;
+        };
+        let stats = self.player.stats();
+        let aim = (aim - self.player.pos).normalize();
+        let count = stats.projectiles + 1;
+        for i in 0..count {
+            let offset = (i as f32 - (count - 1) as f32 / 2.0) * stats.spread.to_radians();
+            let delta = Vec2::from_angle(offset).rotate(aim);
+            let mut projectile = held.projectile.clone();
+            projectile.pos = self.player.pos + delta * 10.0;
+            projectile.direction = delta;
+            projectile.player_owned = true;
+            if let Some(pierce) = &mut projectile.pierce {
+                *pierce += stats.pierce;
+            }
+            projectile.bounces += stats.bounces;
+            projectile.stats = Some(stats.clone());
+            if let Some(profile) = &held.charge {
+                projectile.damage_scale = ChargeProfile::scale(profile.damage, charge);
+                projectile.speed *= ChargeProfile::scale(profile.speed, charge);
+                projectile.radius *= ChargeProfile::scale(profile.size, charge);
+            }
+            projectile.origin = projectile.pos;
+            projectile.spawn(move_vector);
+            self.projectiles.push(projectile);
+        }
+        true
+    }
+    /// Use the active item's ability. Returns false if the player has no active item
+    fn use_active(&mut self, aim: Vec2, move_vector: Vec2) -> bool {
+        let Some(ability) = self.player.active_item().map(|f| f.ability.clone()) else {
+            return false;
+        };
+        let aim = (aim - self.player.pos).normalize_or(RIGHT);
+        // projectiles the ability spawns use the player's stats, with the ability's damage
+        let stats = self.player.stats();
+        match &ability {
+            Ability::Throw(projectile, damage) => {
+                let mut projectile = projectile.clone();
+                projectile.pos = self.player.pos + aim * 6.0;
+                projectile.direction = aim;
+                projectile.player_owned = true;
+                projectile.stats = Some(stats.clone());
+                projectile.damage = Some(damage.clone());
+                projectile.origin = projectile.pos;
+                projectile.spawn(move_vector);
+                self.projectiles.push(projectile);
+            }
+            Ability::Blink(distance) => {
+                let direction = if move_vector != Vec2::ZERO {
+                    move_vector
+                } else {
+                    aim
+                };
+                let top_left_corner = Vec2::new(4.0, 28.0);
+                let bottom_right_corner = Vec2::new(SCREEN_WIDTH - 4.0, SCREEN_HEIGHT - 8.0);
+                self.player.pos = (self.player.pos + direction * *distance)
+                    .clamp(top_left_corner, bottom_right_corner);
+                // teleported, so don't draw the player sliding across the room
+                self.player.prev_pos = self.player.pos;
+            }
+            Ability::Shield(frames) => self.player.shield_frames = *frames,
+            Ability::Summon(projectile, damage, count) => {
+                for i in 0..*count {
+                    let direction =
+                        Vec2::from_angle(i as f32 * (PI * 2.0) / *count as f32).rotate(aim);
+                    let mut projectile = projectile.clone();
+                    projectile.pos = self.player.pos + direction * 10.0;
+                    projectile.direction = direction;
+                    projectile.player_owned = true;
+                    projectile.stats = Some(stats.clone());
+                    projectile.damage = Some(damage.clone());
+                    projectile.origin = projectile.pos;
+                    projectile.spawn(Vec2::ZERO);
+                    self.projectiles.push(projectile);
+                }
+            }
+        }
+        true
+    }
+    fn update(&mut self, input: &InputFrame) {
+        if input.interact {
+            self.interact();
+        }
+        let (move_vector, speed) = if self.player.roll.0 == 0 {
+            let slow = self.player.charging().map_or(1.0, |f| f.move_speed);
+            (input.move_vector, self.player.stats().move_speed * slow)
+        } else {
+            (self.player.roll.1, 4.0)
+        };
+        let start_y = 28.0;
+
+        let top_left_corner = Vec2::new(4.0, start_y);
+        let bottom_right_corner = Vec2::new(SCREEN_WIDTH - 4.0, SCREEN_HEIGHT - 8.0);
+
+        self.player.pos =
+            (self.player.pos + move_vector * speed).clamp(top_left_corner, bottom_right_corner);
+        let door_start_x = TILES_WIDTH / 2 - 1;
+
+        // go to next room
+        if let GameState::PostRound(_, _) = self.state
+            && self.player.pos.y == 28.0
+            && (door_start_x as f32 * 16.0 + 4.0..door_start_x as f32 * 16.0 + 28.0)
+                .contains(&self.player.pos.x)
+            && input.move_vector.y < 0.0
+        {
+            self.player.pos = Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT - 16.0);
+            self.player.prev_pos = self.player.pos;
+            self.dropped_items.clear();
+            self.enemies.clear();
+            self.projectiles.clear();
+            let e = self.dungeon_manager.spawn_room(&mut self.rng);
+            if let Some(mut e) = e {
+                self.spawn_enemies(&mut e);
+            } else {
+                self.state = GameState::GameOver(0, true);
+                return;
+            }
+            let mut extra_enemies = Vec::new();
+            for curse in self.player.curses() {
+                match *curse {
+                    ChaosCurse::AcidPuddles => {
+                        for _ in 0..2 {
+                            let mut new = acid_puddle();
+                            let rng = &mut self.rng.layouts;
+                            new.pos = Vec2::new(
+                                rng.gen_range(top_left_corner.x, bottom_right_corner.x),
+                                rng.gen_range(top_left_corner.y, bottom_right_corner.y - 48.0),
+                            );
+                            new.origin = new.pos;
+                            self.projectiles.push(new);
+                        }
+                    }
+                    ChaosCurse::BonusEnemies => {
+                        let rng = &mut self.rng.layouts;
+                        let (types, amt) = if rng.gen_range(0, 100) < 50 {
+                            (
+                                &self.dungeon_manager.worlds[self.dungeon_manager.world_index]
+                                    .other,
+                                2,
+                            )
+                        } else {
+                            (
+                                &self.dungeon_manager.worlds[self.dungeon_manager.world_index]
+                                    .miniboss,
+                                1,
+                            )
+                        };
+                        for _ in 0..amt {
+                            let ty = select_random(rng, types);
+                            let pos = Vec2::new(
+                                rng.gen_range(top_left_corner.x, bottom_right_corner.x),
+                                rng.gen_range(top_left_corner.y, bottom_right_corner.y - 48.0),
+                            );
+                            let enemy = Enemy::new(ty, pos, 0, &mut self.rng.ai);
+                            extra_enemies.push(enemy);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            self.spawn_enemies(&mut extra_enemies);
+            self.state = GameState::PreRound(0);
+        }
+
+        if move_vector != Vec2::ZERO {
+            self.player.moving = true;
+            self.player.anim_frame += speed;
+        } else {
+            self.player.moving = false;
+        }
+
+        // tick counters
+        if self.player.attack_counter > 0.0 {
+            self.player.attack_counter -= 1.0
+        }
+        if self.player.roll_counter > 0.0 {
+            self.player.roll_counter -= 1.0
+        }
+        if self.player.active_counter > 0.0 {
+            self.player.active_counter -= 1.0
+        }
+        self.player.shield_frames = self.player.shield_frames.saturating_sub(1);
+        self.player.roll.0 = self.player.roll.0.saturating_sub(1);
+        self.player.invuln_frames = self.player.invuln_frames.saturating_sub(1);
+        self.player.perfect_dodge_frames = self.player.perfect_dodge_frames.saturating_sub(1);
+
+        // player combat roll
+        if input.roll && self.player.roll_counter <= 0.0 && self.player.moving {
+            self.player.roll_counter = self.player.stats().roll_delay;
+            self.player.roll = (ROLL_FRAMES, move_vector)
+        }
+
+        // player active item
+        if input.ability
+            && self.player.active_counter <= 0.0
+            && self.use_active(input.aim, move_vector)
+        {
+            self.player.active_counter = self.player.active_cooldown().unwrap_or_default();
+        }
+
+        // player attack. weapons that charge build up charge while the attack is held, and fire
+        // once it's let go
+        let attack = if let Some(profile) = self.player.weapon_charge() {
+            if input.attack && self.player.attack_counter <= 0.0 {
+                let frames = profile.frames;
+                self.player.charge = Some(self.player.charge.map_or(0, |f| (f + 1).min(frames)));
+                None
+            } else {
+                let charge = self.player.charge_fraction();
+                self.player.charge = None;
+                charge
+            }
+        } else {
+            self.player.charge = None;
+            (input.attack && self.player.attack_counter <= 0.0).then_some(0.0)
+        };
+        if let Some(charge) = attack
+            && self.fire_held(input.aim, move_vector, charge)
+        {
+            self.player.attack_counter = self.player.stats().attack_delay;
+            self.player.burst = (self.player.stats().burst, BURST_DELAY);
+        } else if self.player.burst.0 > 0 {
+            // fire the rest of a burst, still following the aim
+            self.player.burst.1 = self.player.burst.1.saturating_sub(1);
+            if self.player.burst.1 == 0 {
+                self.player.burst = (self.player.burst.0 - 1, BURST_DELAY);
+                self.fire_held(input.aim, move_vector, 0.0);
+            }
+        }
+        let mut new_projectiles = Vec::new();
+
+        self.enemy_grid.rebuild(self.enemies.iter().map(|f| f.pos));
+        let mut nearby = Vec::new();
+        let player_stats = self.player.stats();
+        self.projectiles.retain_mut(|projectile| {
+            projectile.life += 1;
+            // home in on the closest enemy nearby that hasn't been hit yet, or the player
+            let target = if !projectile.seeks_target() {
+                None
+            } else if projectile.player_owned {
+                self.enemy_grid
+                    .query(projectile.pos, HOMING_RADIUS, &mut nearby);
+                nearby
+                    .iter()
+                    .map(|index| &self.enemies[*index])
+                    .filter(|enemy| !projectile.hit_enemies.contains(&enemy.id))
+                    .map(|enemy| enemy.pos)
+                    .filter(|pos| pos.distance(projectile.pos) <= HOMING_RADIUS)
+                    .min_by(|a, b| {
+                        a.distance_squared(projectile.pos)
+                            .total_cmp(&b.distance_squared(projectile.pos))
+                    })
+            } else {
+                Some(self.player.pos)
+            };
+            if !projectile.tick(target, &mut new_projectiles) {
+                return false;
+            }
+            // split the projectiles movement in to chunks so that projectiles moving fast wont pass through enemies
+            let speed_chunks = if projectile.speed <= 6.0 {
+                vec![projectile.speed]
+            } else {
+                let amt = (projectile.speed / 6.0).floor() + 1.0;
+                vec![projectile.speed / amt; amt as usize]
+            };
+            for speed in speed_chunks.into_iter() {
+                projectile.pos += projectile.direction * speed;
+                if !projectile.moved() {
+                    return false;
+                }
+
+                // check for collisions
+                if projectile.player_owned && projectile.stats.is_some() {
+                    let mut spent = false;
+                    let reach = projectile.radius * 1.5;
+                    self.enemy_grid.query(projectile.pos, reach, &mut nearby);
+                    for index in nearby.iter() {
+                        let enemy = &mut self.enemies[*index];
+                        if projectile.hits_enemies()
+                            && !projectile.hit_enemies.contains(&enemy.id)
+                            && (enemy.pos - projectile.pos).length() <= reach
+                            && let Some(stats) = &projectile.stats
+                        {
+                            // todo: make projectiles moving faster than 8.0 pixels/frame have their hit scan split in to multiple steps
+                            let mut impact_damage = Vec::new();
+                            let crit = stats.crit_chance > 0.0
+                                && self.rng.ai.gen_range(0.0, 1.0) < stats.crit_chance;
+                            // deal the damage types in a fixed order, since a shield takes
+                            // whichever comes first
+                            for k in enum_iterator::all::<DamageType>() {
+                                let Some(mut amt) = projectile.damage(k) else {
+                                    continue;
+                                };
+                                if let Some(modifier) = player_stats.damage_modifiers.get(&k) {
+                                    amt *= 1.0 + modifier;
+                                }
+                                if crit {
+                                    amt *= 1.0 + stats.crit_damage_mod;
+                                }
+                                if stats.impact_damage_mod > 0.0 {
+                                    impact_damage.push((k, amt * stats.impact_damage_mod));
+                                }
+                                let amt = enemy.resisted(k, amt);
+                                let kind = if amt <= 0.0 {
+                                    HitKind::Immune
+                                } else if enemy.shield > 0.0 {
+                                    HitKind::Shield
+                                } else if crit {
+                                    HitKind::Crit
+                                } else {
+                                    HitKind::Normal
+                                };
+                                self.hits.push(Hit {
+                                    enemy_id: enemy.id,
+                                    pos: enemy.pos,
+                                    ty: k,
+                                    amount: amt,
+                                    kind,
+                                });
+                                *self.damage_dealt.entry(k).or_default() += amt;
+                                enemy.take_damage(amt);
+                            }
+                            // roll for the status effects of each damage type dealt, in a fixed
+                            // order so runs stay deterministic
+                            for ty in enum_iterator::all::<DamageType>() {
+                                if !projectile.damage(ty).is_some_and(|f| f > 0.0) {
+                                    continue;
+                                }
+                                let Some(chances) = stats.status_chances.get(&ty) else {
+                                    continue;
+                                };
+                                for effect in enum_iterator::all::<StatusEffectType>() {
+                                    if let Some(chance) = chances.get(&effect)
+                                        && self.rng.ai.gen_range(0.0, 1.0) < *chance
+                                    {
+                                        enemy.apply_status(effect);
+                                    }
+                                }
+                            }
+                            if projectile.knockback > 0.0 {
+                                enemy.knock_back(
+                                    projectile.direction * projectile.knockback,
+                                    impact_damage,
+                                );
+                            }
+                            enemy.damage_frames = 5;
+                            if crit {
+                                enemy.crit_frames = CRIT_FRAMES;
+                            }
+                            projectile.hit_enemies.push(enemy.id);
+                            // dont allow projectile children to trigger on hit effects on the same enemy
+                            if !projectile.parent_hit_enemies.contains(&enemy.id) {
+                                new_projectiles.append(&mut projectile.on_hit(crit));
+                            }
+                            if !projectile.hit_enemy() {
+                                spent = true;
+                                break;
+                            }
+                        }
+                    }
+                    if spent {
+                        return false;
+                    }
+                } else {
+                    let distance = (self.player.pos - projectile.pos).length();
+                    if distance > projectile.radius {
+                        continue;
+                    }
+                    if self.player.can_take_damage() {
+                        if self.player.damage() {
+                            self.state = GameState::GameOver(0, false);
+                        }
+                    } else if self.player.can_perfect_dodge() {
+                        // rolled through it just in time, so the next roll and attack are ready
+                        self.player.perfect_dodge_frames = PERFECT_DODGE_FRAMES;
+                        self.player.roll_counter = 0.0;
+                        self.player.attack_counter = 0.0;
+                    }
+                }
+            }
+            let old = projectile.pos;
+            projectile.pos = projectile.pos.clamp(top_left_corner, bottom_right_corner);
+            if projectile.pos != old && !projectile.hit_wall(old) {
+                // projectile broke on the wall
+                new_projectiles.append(&mut projectile.on_hit(false));
+                return false;
+            }
+
+            if projectile.life >= projectile.lifetime {
+                projectile.expire(&mut new_projectiles);
+                return false;
+            }
+            true
+        });
+        // the player's melee attacks destroy or send back the enemy projectiles they touch
+        let parries: Vec<Projectile> = self
+            .projectiles
+            .iter()
+            .filter(|f| f.player_owned && f.parries)
+            .cloned()
+            .collect();
+        if !parries.is_empty() {
+            self.projectiles.retain_mut(|projectile| {
+                if projectile.player_owned {
+                    return true;
+                }
+                let Some(parrier) = parries
+                    .iter()
+                    .find(|f| f.pos.distance(projectile.pos) <= f.radius + projectile.radius)
+                else {
+                    return true;
+                };
+                if projectile.reflectable {
+                    projectile.reflect(parrier);
+                    return true;
+                }
+                !projectile.destructible
+            });
+        }
+        self.projectiles.append(&mut new_projectiles);
+        let enemy_positions: Vec<Vec2> = self.enemies.iter().map(|f| f.pos).collect();
+        let mut deaths = Vec::new();
+
+        self.enemies.retain_mut(|enemy| {
+            let player_delta = self.player.pos - enemy.pos;
+            enemy.damage_frames = enemy.damage_frames.saturating_sub(1);
+            enemy.crit_frames = enemy.crit_frames.saturating_sub(1);
+            enemy.hit_stun = enemy.hit_stun.saturating_sub(1);
+            for (ty, amt) in enemy.tick_status_effects() {
+                self.hits.push(Hit::dealt(enemy, ty, amt));
+                *self.damage_dealt.entry(ty).or_default() += amt;
+            }
+            let sliding = enemy.sliding();
+            if sliding {
+                enemy.slide();
+            }
+            let mut move_direction = Vec2::ZERO;
+            let phase = enemy.get_phase();
+            // move
+            match &phase.movement {
+                EnemyMovement::Chase => {
+                    enemy.direction = player_delta.normalize();
+                    move_direction = enemy.direction;
+                }
+                EnemyMovement::Wander(face_player) => {
+                    let mut new_target = true;
+                    if let Some(move_target) = enemy.move_target {
+                        new_target = false;
+                        let delta = move_target - enemy.pos;
+                        move_direction = delta.normalize();
+                        if *face_player {
+                            enemy.direction = player_delta.normalize();
+                        } else {
+                            enemy.direction = move_direction;
+                        }
+                        if delta.length() <= 4.0 {
+                            new_target = true;
+                        }
+                    }
+                    if new_target {
+                        // set new move target if either no previous move target was set,
+                        // or distance was less than 4.0
+                        enemy.move_target = Some(Vec2::new(
+                            self.rng
+                                .ai
+                                .gen_range(top_left_corner.x, bottom_right_corner.x),
+                            self.rng
+                                .ai
+                                .gen_range(top_left_corner.y, bottom_right_corner.y),
+                        ));
+                    }
+                }
+                EnemyMovement::Still => {}
+                EnemyMovement::Fowards => {
+                    move_direction = enemy.direction;
+                }
+            }
+            // the grid was built before projectiles moved, and enemies haven't moved since
+            self.enemy_grid.query(enemy.pos, 7.0, &mut nearby);
+            for pos in nearby.iter().map(|f| &enemy_positions[*f]) {
+                if pos != &enemy.pos {
+                    let delta = enemy.pos - *pos;
+                    if delta.length() <= 7.0 {
+                        move_direction = delta.normalize();
+                    }
+                }
+            }
+            let speed = enemy.ty.speed * enemy.move_multiplier();
+            enemy.pos += move_direction * speed;
+            enemy.anim_frame += speed;
+            // shoot
+            let attack_speed = enemy.attack_speed_multiplier();
+            if enemy.attack_counter > 0.0 {
+                enemy.attack_counter -= attack_speed;
+            } else if attack_speed > 0.0 {
+                match &phase.firing {
+                    ProjectileFiring::Forwards(projectile, delay) => {
+                        enemy.attack_counter = *delay as f32;
+                        let mut projectile = projectile.clone();
+                        projectile.pos = enemy.pos;
+                        projectile.direction = enemy.direction;
+                        projectile.player_owned = false;
+                        projectile.origin = projectile.pos;
+                        projectile.spawn(Vec2::ZERO);
+                        self.projectiles.push(projectile);
+                    }
+                    ProjectileFiring::Around(projectile, delay, amt) => {
+                        enemy.attack_counter = *delay as f32;
+                        for i in 0..*amt {
+                            let angle =
+                                enemy.direction.to_angle() + i as f32 * (PI * 2.0) / *amt as f32;
+                            let direction = Vec2::from_angle(angle);
+                            let mut projectile = projectile.clone();
+                            projectile.pos = enemy.pos;
+                            projectile.direction = direction;
+                            projectile.player_owned = false;
+                            projectile.origin = projectile.pos;
+                            projectile.spawn(Vec2::ZERO);
+                            self.projectiles.push(projectile);
+                        }
+                    }
+                    ProjectileFiring::None => {}
+                }
+            }
+            let mut collision = false;
+
+            let old = enemy.pos;
+            enemy.pos = enemy.pos.clamp(top_left_corner, bottom_right_corner);
+            if enemy.pos != old {
+                collision = true;
+                if sliding {
+                    for (ty, amt) in enemy.slam() {
+                        self.hits.push(Hit::dealt(enemy, ty, amt));
+                        *self.damage_dealt.entry(ty).or_default() += amt;
+                    }
+                }
+            }
+
+            // dmg player on contact
+            if player_delta.length() <= 4.0 && self.player.can_take_damage() {
+                if self.player.damage() {
+                    self.state = GameState::GameOver(0, false);
+                }
+                collision = true;
+            }
+
+            // check if phase should change
+            let phase_end = match &phase.end {
+                PhaseEndCondition::None => false,
+                PhaseEndCondition::SingleFrame => true,
+                PhaseEndCondition::Collision => collision,
+                PhaseEndCondition::PlayerDistance(dist) => player_delta.length() <= *dist,
+                PhaseEndCondition::Frames(target) => {
+                    if enemy.phase_frame_counter >= *target {
+                        enemy.phase_frame_counter = 0;
+                        true
+                    } else {
+                        enemy.phase_frame_counter += 1;
+                        false
+                    }
+                }
+                PhaseEndCondition::HealthUnder(percent) => {
+                    enemy.health / enemy.ty.max_health <= *percent
+                }
+            };
+            if phase_end {
+                enemy.phase_index += 1;
+                if enemy.phase_index >= enemy.ty.phases.len() {
+                    enemy.phase_index = 0;
+                }
+            }
+
+            if enemy.health <= 0.0 {
+                deaths.push((enemy.ty, enemy.pos));
+                return false;
+            }
+            true
+        });
+        // enemies spawned by death effects keep the room from being cleared
+        for (ty, pos) in deaths {
+            self.on_death(ty, pos);
+        }
+
+        if let GameState::RoundActive = self.state
+            && self.enemies.is_empty()
+        {
+            self.projectiles.retain(|f| f.player_owned);
+            self.state = GameState::PostRound(
+                0,
+                Some(Box::new(std::array::from_fn(|_| self.get_item_reward()))),
+            )
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Uniform grid over the room, for finding the things near a point without checking every one.
+
+use macroquad::prelude::*;
+
+use crate::utils::*;
+
+/// Width and height of a cell in pixels
+const CELL_SIZE: f32 = 16.0;
+const COLUMNS: usize = (SCREEN_WIDTH / CELL_SIZE) as usize;
+const ROWS: usize = (SCREEN_HEIGHT / CELL_SIZE) as usize;
+
+/// Indices of things in a list, sorted in to the cells their positions are in.
+/// Has to be rebuilt whenever the things move or the list changes.
+pub struct SpatialGrid {
+    cells: Vec<Vec<usize>>,
+}
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self {
+            cells: vec![Vec::new(); COLUMNS * ROWS],
+        }
+    }
+}
+impl SpatialGrid {
+    /// Clear the grid and add the positions in it, by their index. Positions outside the room
+    /// go in the closest cell.
+    pub fn rebuild(&mut self, positions: impl Iterator<Item = Vec2>) {
+        for cell in self.cells.iter_mut() {
+            cell.clear();
+        }
+        for (index, pos) in positions.enumerate() {
+            let (x, y) = Self::cell(pos);
+            self.cells[y * COLUMNS + x].push(index);
+        }
+    }
+    /// Replace `out` with the indices of everything in the cells a circle touches, in
+    /// ascending order so they're gone through in the same order as the list. Some may be
+    /// further away than the radius, so the distance still has to be checked.
+    pub fn query(&self, pos: Vec2, radius: f32, out: &mut Vec<usize>) {
+        out.clear();
+        let (min_x, min_y) = Self::cell(pos - radius);
+        let (max_x, max_y) = Self::cell(pos + radius);
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                out.extend_from_slice(&self.cells[y * COLUMNS + x]);
+            }
+        }
+        out.sort_unstable();
+    }
+    fn cell(pos: Vec2) -> (usize, usize) {
+        let x = (pos.x / CELL_SIZE).clamp(0.0, (COLUMNS - 1) as f32);
+        let y = (pos.y / CELL_SIZE).clamp(0.0, (ROWS - 1) as f32);
+        (x as usize, y as usize)
+    }
+}
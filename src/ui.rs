@@ -2,28 +2,35 @@ use macroquad::prelude::*;
 
 use crate::{
     assets::Assets,
+    enemy::EnemyType,
     items::{Item, ItemType},
-    player::{ChaosCurse, Player},
+    player::{ChaosCurse, InventorySlot, Player},
+    projectiles::DamageType,
+    simulation::InventoryAction,
     utils::*,
 };
 
+/// Enemies listed on each page of the bestiary
+pub const BESTIARY_PAGE_SIZE: usize = 16;
+
 #[derive(Default)]
 pub struct UiManager {
     pub inv_open: bool,
-    pub cursor_item: Option<Item>,
+    /// Disables opening the inventory and interacting with it, used during replays
+    pub read_only: bool,
 }
 
 impl UiManager {
     #[must_use]
-    /// Update and draw UI. Returns if any item should be dropped.
+    /// Update and draw UI. Returns the inventory action the player made, if any.
     pub fn update(
         &mut self,
         assets: &Assets,
-        player: &mut Player,
+        player: &Player,
         mouse_x: f32,
         mouse_y: f32,
         ui_width: f32,
-    ) -> Option<Item> {
+    ) -> Option<InventoryAction> {
         let max = player.stats().max_lives;
         for i in 0..max {
             let sprite = if i < player.stats().lives { 0.0 } else { 1.0 };
@@ -35,7 +42,21 @@ impl UiManager {
                 None,
             );
         }
-        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::F) {
+        // active item left of the hearts, darkened from the top while it's on cooldown
+        if let Some(active) = player.slot(InventorySlot::Active) {
+            let x = SCREEN_WIDTH / 2.0 - 8.0 * max as f32 - 14.0;
+            let y = SCREEN_HEIGHT - 14.0;
+            draw_slot(Some(active), x, y, 0.0, 0.0, assets);
+            if let Some(cooldown) = player.active_cooldown()
+                && player.active_counter > 0.0
+            {
+                let fraction = (player.active_counter / cooldown).min(1.0);
+                let mut color = Color::from_vec(COLORS[1]);
+                color.a = 0.6;
+                draw_rectangle(x + 1.0, y + 1.0, 10.0, (10.0 * fraction).ceil(), color);
+            }
+        }
+        if !self.read_only && (is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::F)) {
             self.inv_open = !self.inv_open;
         }
 
@@ -50,98 +71,136 @@ impl UiManager {
             let sx = x + 2.0 + 12.0;
             let sy = y + 2.0 + 12.0;
             player.draw_character(sx, sy, assets, 0.0, None);
-            if let Some(held) = &player.hand {
-                assets
-                    .items
-                    .draw_sprite(sx + 3.0, sy + 2.0, held.sprite_x, held.sprite_y, None);
+            if let Some(held) = player.slot(InventorySlot::Hand) {
+                assets.item_sheet(held.sheet).draw_sprite(
+                    sx + 3.0,
+                    sy + 2.0,
+                    held.sprite_x,
+                    held.sprite_y,
+                    None,
+                );
             }
             let mut hovered = None;
 
             let sx = x + 2.0 + 25.0 + 2.0;
             let mut sy = y + 2.0;
             // helmet
-            let helmet_is_none = player.helmet.is_none();
-            if draw_slot(player.helmet.as_ref(), sx, sy, mouse_x, mouse_y, assets)
-                && self
-                    .cursor_item
-                    .as_ref()
-                    .is_none_or(|f| matches!(f.ty, ItemType::Helmet))
+            let helmet_is_none = player.slot(InventorySlot::Helmet).is_none();
+            if draw_slot(
+                player.slot(InventorySlot::Helmet),
+                sx,
+                sy,
+                mouse_x,
+                mouse_y,
+                assets,
+            ) && player
+                .cursor_item()
+                .is_none_or(|f| matches!(f.ty, ItemType::Helmet))
             {
-                hovered = Some(&mut player.helmet);
+                hovered = Some(InventorySlot::Helmet);
             }
             if helmet_is_none {
                 assets.items.draw_sprite(sx + 6.0, sy + 6.0, 0.0, 1.0, None);
             }
 
             // chestplate
-            let chestplate_is_none = player.chestplate.is_none();
+            let chestplate_is_none = player.slot(InventorySlot::Chestplate).is_none();
             sy += 12.0 + 1.0;
-            if draw_slot(player.chestplate.as_ref(), sx, sy, mouse_x, mouse_y, assets)
-                && self
-                    .cursor_item
-                    .as_ref()
-                    .is_none_or(|f| matches!(f.ty, ItemType::Chestplate))
+            if draw_slot(
+                player.slot(InventorySlot::Chestplate),
+                sx,
+                sy,
+                mouse_x,
+                mouse_y,
+                assets,
+            ) && player
+                .cursor_item()
+                .is_none_or(|f| matches!(f.ty, ItemType::Chestplate))
             {
-                hovered = Some(&mut player.chestplate)
+                hovered = Some(InventorySlot::Chestplate)
             }
             if chestplate_is_none {
                 assets.items.draw_sprite(sx + 6.0, sy + 6.0, 0.0, 0.0, None);
             }
 
             // hand
-            let hand_is_none = player.hand.is_none();
+            let hand_is_none = player.slot(InventorySlot::Hand).is_none();
             let sx = x + 2.0;
             let sy = y + 25.0 + 3.0;
-            if draw_slot(player.hand.as_ref(), sx, sy, mouse_x, mouse_y, assets)
-                && self
-                    .cursor_item
-                    .as_ref()
-                    .is_none_or(|f| matches!(f.ty, ItemType::Held(_)))
+            if draw_slot(
+                player.slot(InventorySlot::Hand),
+                sx,
+                sy,
+                mouse_x,
+                mouse_y,
+                assets,
+            ) && player
+                .cursor_item()
+                .is_none_or(|f| matches!(f.ty, ItemType::Held(_)))
             {
-                hovered = Some(&mut player.hand)
+                hovered = Some(InventorySlot::Hand)
             }
             if hand_is_none {
                 assets.items.draw_sprite(sx + 6.0, sy + 6.0, 0.0, 2.0, None);
             }
 
+            // active item
+            let active_is_none = player.slot(InventorySlot::Active).is_none();
+            let sx = sx + 12.0 + 1.0;
+            if draw_slot(
+                player.slot(InventorySlot::Active),
+                sx,
+                sy,
+                mouse_x,
+                mouse_y,
+                assets,
+            ) && player
+                .cursor_item()
+                .is_none_or(|f| matches!(f.ty, ItemType::Active(_)))
+            {
+                hovered = Some(InventorySlot::Active)
+            }
+            if active_is_none {
+                assets.items.draw_sprite(sx + 6.0, sy + 6.0, 0.0, 4.0, None);
+            }
+
             // talismans
-            for (index, slot) in player.talismans.iter_mut().enumerate() {
+            for (index, slot) in player.talismans().iter().enumerate() {
                 let sx = x + width - 2.0 - 12.0;
                 let sy = y + 2.0 + (12.0 + 1.0) * index as f32;
                 draw_ui_rect(sx, sy, 12.0, 12.0);
                 let is_none = slot.is_none();
                 if draw_slot(slot.as_ref(), sx, sy, mouse_x, mouse_y, assets)
-                    && self
-                        .cursor_item
-                        .as_ref()
+                    && player
+                        .cursor_item()
                         .is_none_or(|f| matches!(f.ty, ItemType::Talisman))
                 {
-                    hovered = Some(slot)
+                    hovered = Some(InventorySlot::Talisman(index))
                 }
                 if is_none {
                     assets.items.draw_sprite(sx + 6.0, sy + 6.0, 0.0, 3.0, None);
                 }
             }
             let inventory_curse_count = player
-                .curses
+                .curses()
                 .iter()
                 .filter(|f| matches!(*f, ChaosCurse::LessInventory))
                 .count();
 
             // inventory
-            for (index, slot) in player.inventory.iter_mut().enumerate() {
+            for (index, slot) in player.inventory().iter().enumerate() {
                 let sx = x + 2.0 + (12.0 + 2.0) * index as f32;
                 let sy = y + height - 2.0 - 12.0;
                 draw_ui_rect(sx, sy, 12.0, 12.0);
                 if index < inventory_curse_count * 2 {
                     draw_line(sx + 12.0, sy, sx, sy + 12.0, 1.0, Color::from_hex(0xda2424));
                 } else if draw_slot(slot.as_ref(), sx, sy, mouse_x, mouse_y, assets) {
-                    hovered = Some(slot)
+                    hovered = Some(InventorySlot::Inventory(index))
                 }
             }
-            if self.cursor_item.is_some() {
+            if player.cursor_item().is_some() {
                 draw_slot(
-                    self.cursor_item.as_ref(),
+                    player.cursor_item(),
                     mouse_x - 6.0,
                     mouse_y - 6.0,
                     0.0,
@@ -149,30 +208,30 @@ impl UiManager {
                     assets,
                 );
             }
-            if self.cursor_item.is_none()
-                && let Some(Some(item)) = hovered
+            if player.cursor_item().is_none()
+                && let Some(hovered) = hovered
+                && let Some(item) = player.slot(hovered)
             {
-                draw_hover_item(&item, mouse_x, mouse_y, assets);
+                draw_hover_item(item, mouse_x, mouse_y, assets);
             }
 
             if is_mouse_button_pressed(MouseButton::Left) {
                 if let Some(hovered) = hovered {
                     // if a slot is hovered,  replace it with the cursor item
-
-                    std::mem::swap(&mut self.cursor_item, hovered);
+                    return Some(InventoryAction::Swap(hovered));
                 } else if (!(x..x + width).contains(&mouse_x)
                     || !(y..y + height).contains(&mouse_y))
-                    && let Some(cursor_item) = self.cursor_item.take()
+                    && player.cursor_item().is_some()
                 {
                     // if cursor is outside inventory, and the cursor item isnt None, drop it.
-                    return Some(cursor_item);
+                    return Some(InventoryAction::Drop(Vec2::new(mouse_x, mouse_y)));
                 }
             }
         }
 
         let width = 19.0 * 4.0 + 8.0 + 4.0;
         let height = 12.0;
-        for (index, curse) in player.curses.iter().rev().enumerate() {
+        for (index, curse) in player.curses().iter().rev().enumerate() {
             let x = ui_width - (ui_width - SCREEN_WIDTH) / 2.0 - width;
             let y = index as f32 * (height - 1.0);
             draw_ui_rect(x, y, width, height);
@@ -206,6 +265,48 @@ pub fn draw_hover_item(item: &Item, x: f32, y: f32, assets: &Assets) {
     }
 }
 
+/// Draw a page of the bestiary, which lists each enemy with its health, and how much more or
+/// less damage it takes of each type
+pub fn draw_bestiary(enemies: &[&EnemyType], page: usize, assets: &Assets) {
+    let width = 124.0;
+    let height = 23.0;
+    let entries = enemies
+        .iter()
+        .skip(page * BESTIARY_PAGE_SIZE)
+        .take(BESTIARY_PAGE_SIZE);
+    for (index, ty) in entries.enumerate() {
+        let x = 3.0 + (index % 2) as f32 * (width + 3.0);
+        let y = 14.0 + (index / 2) as f32 * (height + 1.0);
+        draw_ui_rect(x, y, width, height);
+        let phase = &ty.phases[0];
+        assets.entity_sheet(ty.sheet).draw_sprite(
+            x + 10.0,
+            y + 10.0,
+            phase.sprite_x,
+            phase.sprite_y,
+            None,
+        );
+
+        let mut lines = vec![ty.name.clone(), format!("\x02H\x01 {}", ty.max_health)];
+        let resistances: Vec<String> = enum_iterator::all::<DamageType>()
+            .filter_map(|dt| {
+                let resistance = *ty.resistances.get(&dt)?;
+                // resisted damage is green, and damage the enemy is weak to is red
+                let color = if resistance > 0.0 { "\x04" } else { "\x02" };
+                Some(format!(
+                    "{color}{} {:+}%\x01",
+                    dt.to_text(),
+                    (-resistance * 100.0).round()
+                ))
+            })
+            .collect();
+        for pair in resistances.chunks(2) {
+            lines.push(pair.join(" "));
+        }
+        assets.draw_text(&lines.join("\n"), x + 20.0, y + 2.0);
+    }
+}
+
 pub fn draw_item_tooltip(item: &Item, assets: &Assets) {
     draw_hover_item(
         item,
@@ -235,13 +336,21 @@ pub fn draw_slot(
     let hovered = (x..x + 12.0).contains(&mouse_x) && (y - 1.0..y + 12.0 + 1.0).contains(&mouse_y);
     if let Some(item) = item {
         if let Some(e) = &item.enchantment {
-            assets
-                .enchantments
-                .draw_sprite(x + 6.0, y + 6.0, e.sprite_x, e.sprite_y, None);
+            assets.enchantment_sheet(e.sheet).draw_sprite(
+                x + 6.0,
+                y + 6.0,
+                e.sprite_x,
+                e.sprite_y,
+                None,
+            );
         }
-        assets
-            .items
-            .draw_sprite(x + 6.0, y + 6.0, item.sprite_x, item.sprite_y, None);
+        assets.item_sheet(item.sheet).draw_sprite(
+            x + 6.0,
+            y + 6.0,
+            item.sprite_x,
+            item.sprite_y,
+            None,
+        );
     }
     hovered
 }
@@ -267,6 +376,44 @@ pub fn draw_button(
     hovered && is_mouse_button_pressed(MouseButton::Left)
 }
 
+/// Draw a text field that only accepts digits. Clicking the field focuses it,
+/// clicking anywhere else or pressing enter unfocuses it.
+#[expect(clippy::too_many_arguments)]
+pub fn draw_number_field(
+    label: &str,
+    text: &mut String,
+    focused: &mut bool,
+    assets: &Assets,
+    x: f32,
+    y: f32,
+    width: f32,
+    mouse_x: f32,
+    mouse_y: f32,
+) {
+    let height = 8.0;
+    let hovered = (x..x + width).contains(&mouse_x) && (y..y + height).contains(&mouse_y);
+    if is_mouse_button_pressed(MouseButton::Left) {
+        *focused = hovered;
+    }
+    if is_key_pressed(KeyCode::Enter) {
+        *focused = false;
+    }
+    while let Some(char) = get_char_pressed() {
+        if *focused && char.is_ascii_digit() && text.len() < 10 {
+            text.push(char);
+        }
+    }
+    if *focused && is_key_pressed(KeyCode::Backspace) {
+        text.pop();
+    }
+
+    draw_ui_rect(x, y, width, height);
+    assets.draw_text(&format!("{label}{text}"), x + 2.0, y + 2.0);
+    if *focused || hovered {
+        draw_rectangle_lines(x, y, width, height, 2.0, Color::from_hex(0x8a4926));
+    }
+}
+
 pub fn draw_ui_rect(x: f32, y: f32, w: f32, h: f32) {
     draw_rectangle(x, y, w, h, Color::from_hex(0x1e090d));
     draw_rectangle(
@@ -5,12 +5,20 @@ use macroquad::{
     prelude::*,
 };
 
+use crate::rng::Rng;
+
 pub const SCREEN_WIDTH: f32 = 256.0;
 pub const SCREEN_HEIGHT: f32 = 224.0;
 
 pub const TILES_WIDTH: u32 = 16;
 pub const TILES_HEIGHT: u32 = 9;
 
+/// Seconds per simulation step
+pub const TIMESTEP: f64 = 1.0 / 60.0;
+/// Most steps run in a single frame when catching up. Any more time than that is dropped,
+/// so a long hitch slows the game down instead of skipping ahead.
+pub const MAX_STEPS_PER_FRAME: u32 = 5;
+
 pub const PREROUND_TRANSITION_TIME: u32 = 40;
 pub const PREROUND_GRACE_TIME: u32 = 20;
 
@@ -19,11 +27,21 @@ pub const ENCHANT_CHANCE: u8 = 10;
 
 pub const INV_SLOTS: usize = 9;
 
+/// Frames between the shots of a burst
+pub const BURST_DELAY: u8 = 6;
+
+/// Frames a roll lasts for
+pub const ROLL_FRAMES: u8 = 12;
+/// Frames at the start of a roll an enemy projectile can be perfectly dodged in
+pub const PERFECT_DODGE_WINDOW: u8 = 4;
+/// Frames the flash after a perfect dodge is shown for
+pub const PERFECT_DODGE_FRAMES: u8 = 12;
+
 pub const RIGHT: Vec2 = Vec2::new(1.0, 0.0);
 
 /// Select random entry from list. Panics if empty
-pub fn select_random<T>(items: &[T]) -> &T {
-    &items[rand::gen_range(0, items.len())]
+pub fn select_random<'a, T>(rng: &mut Rng, items: &'a [T]) -> &'a T {
+    &items[rng.gen_range(0, items.len())]
 }
 
 pub fn replace_pascal_case(text: &str) -> String {
@@ -46,13 +64,12 @@ pub fn replace_pascal_case(text: &str) -> String {
 pub fn create_camera(w: f32, h: f32) -> Camera2D {
     let rt = render_target(w as u32, h as u32);
     rt.texture.set_filter(FilterMode::Nearest);
-    let cam = Camera2D {
+    Camera2D {
         render_target: Some(rt),
         zoom: Vec2::new(1.0 / w * 2.0, 1.0 / h * 2.0),
         target: Vec2::new(w / 2.0, h / 2.0),
         ..Default::default()
-    };
-    cam
+    }
 }
 
 pub static COLORS: &[Vec4] = &[
@@ -62,6 +79,10 @@ pub static COLORS: &[Vec4] = &[
     Color::from_hex(0x2890dc).to_vec(),
     Color::from_hex(0x08b23b).to_vec(),
     Color::from_hex(0x720d0d).to_vec(),
+    Color::from_hex(0xef6e10).to_vec(),
+    Color::from_hex(0xecab11).to_vec(),
+    Color::from_hex(0xc12458).to_vec(),
+    Color::from_hex(0x5ee9e9).to_vec(),
 ];
 
 pub static COLOR_MOD_MATERIAL: LazyLock<Material> = LazyLock::new(|| {
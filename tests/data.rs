@@ -0,0 +1,97 @@
+//! Loads enemy types from data files written by a mod.
+
+use std::time::{Duration, Instant};
+
+use ramble::{
+    assets::SheetId,
+    data::{DataFile, load_enemy_types},
+};
+
+fn enemies_file(text: String) -> DataFile {
+    DataFile {
+        path: "enemies.ron".into(),
+        text,
+        from_mod: Some("test".into()),
+        sheet: SheetId::BuiltIn,
+    }
+}
+
+/// An enemy type, with the death effects written as they are in the data file
+fn enemy(name: &str, on_death: &str) -> String {
+    format!(
+        "(name: \"{name}\", speed: 1.0, max_health: 10.0, \
+        phases: [(sprite: (2, 1), frames: 2, movement: Chase)], on_death: [{on_death}]),"
+    )
+}
+
+#[test]
+fn long_chains_of_splits_load_quickly() {
+    // every type splits twice in to the next one, and once in to the one after that
+    let count = 40;
+    let mut text = String::from("[");
+    for index in 0..count {
+        let split = |to: usize| format!("Split(enemy: \"e{to}\", count: 2),");
+        let on_death = match count - 1 - index {
+            0 => String::new(),
+            1 => split(index + 1).repeat(2),
+            _ => split(index + 1).repeat(2) + &split(index + 2),
+        };
+        text += &enemy(&format!("e{index}"), &on_death);
+    }
+    text += "]";
+
+    let start = Instant::now();
+    let types = load_enemy_types(&[enemies_file(text)]).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(types.len(), count);
+    assert_eq!(types[0].on_death.len(), 3);
+}
+
+#[test]
+fn enemies_that_split_in_to_themselves_are_reported() {
+    let text = format!(
+        "[{}{}{}]",
+        enemy("a", "Split(enemy: \"b\", count: 2)"),
+        enemy("b", "Split(enemy: \"c\", count: 2)"),
+        enemy("c", "Split(enemy: \"a\", count: 1)"),
+    );
+    let err = load_enemy_types(&[enemies_file(text)]).err().unwrap();
+    assert!(err.contains("splits in to itself"), "{err}");
+}
+
+#[test]
+fn numbers_that_would_break_enemies_are_reported() {
+    for (fields, err) in [
+        ("speed: 1.0, max_health: 0.0", "max_health: must be above 0"),
+        (
+            "speed: 1.0, max_health: NaN",
+            "max_health: must be a finite number",
+        ),
+        ("speed: -1.0, max_health: 10.0", "speed: can't be negative"),
+        (
+            "speed: inf, max_health: 10.0",
+            "speed: must be a finite number",
+        ),
+        (
+            "speed: 1.0, max_health: 10.0, weight: 0.0",
+            "weight: must be above 0",
+        ),
+        (
+            "speed: 1.0, max_health: 10.0, poise: -1.0",
+            "poise: can't be negative",
+        ),
+    ] {
+        let text = format!(
+            "[(name: \"a\", {fields}, phases: [(sprite: (2, 1), frames: 2, movement: Chase)])]"
+        );
+        let result = load_enemy_types(&[enemies_file(text)]);
+        assert!(result.err().is_some_and(|f| f.contains(err)), "{fields}");
+    }
+
+    let text = "[(name: \"a\", speed: 1.0, max_health: 10.0, phases: [(sprite: (2, 1), \
+        frames: 2, movement: Chase, firing: Forwards(projectile: \"arrow\", delay: 0))])]";
+    let err = load_enemy_types(&[enemies_file(text.into())])
+        .err()
+        .unwrap();
+    assert!(err.contains("firing.delay: must be at least 1"), "{err}");
+}
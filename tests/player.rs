@@ -0,0 +1,37 @@
+//! Equips, unequips and curses the player, and checks their stats keep up.
+
+use macroquad::prelude::*;
+use ramble::{items::*, player::*, projectiles::DamageType, simulation::*, utils::*};
+
+#[test]
+fn stats_follow_items_and_curses() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let player = Player::new(Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0));
+    let mut sim = Simulation::new(&items, &enchantments, player, 0);
+    let max_lives = sim.player.stats().max_lives;
+    let move_speed = sim.player.stats().move_speed;
+
+    sim.player
+        .give_item(get_item_by_name(&items, "chainmail").unwrap().clone());
+    assert_eq!(sim.player.stats().max_lives, max_lives + 1);
+
+    // taking it off puts it on the cursor, which doesn't count
+    sim.apply_inventory_action(&InventoryAction::Swap(InventorySlot::Chestplate));
+    assert!(sim.player.cursor_item().is_some());
+    assert_eq!(sim.player.stats().max_lives, max_lives);
+
+    *sim.player.slot_mut(InventorySlot::Helmet).unwrap() =
+        Some(get_item_by_name(&items, "archers hood").unwrap().clone());
+    assert!(sim.player.stats().move_speed > move_speed);
+
+    assert_eq!(
+        sim.player.stats().damage_modifiers.get(&DamageType::Unholy),
+        None
+    );
+    sim.give_curse(ChaosCurse::DoubledUnholyDmg);
+    assert_eq!(
+        sim.player.stats().damage_modifiers.get(&DamageType::Unholy),
+        Some(&1.0)
+    );
+}
@@ -0,0 +1,20 @@
+//! Saves a run between rooms and resumes it.
+
+use macroquad::prelude::*;
+use ramble::{items::*, player::*, simulation::*, utils::*};
+
+#[test]
+fn resuming_keeps_the_active_item_cooling_down() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let mut player = Player::new(Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0));
+    player.give_item(get_item_by_name(&items, "bomb bag").unwrap().clone());
+    player.active_counter = 125.5;
+    let sim = Simulation::new(&items, &enchantments, player, 3);
+
+    let save = sim.to_save().expect("a new run starts between rooms");
+    let resumed = Simulation::from_save(&items, &enchantments, &save).unwrap();
+    assert!(resumed.player.slot(InventorySlot::Active).is_some());
+    assert_eq!(resumed.player.active_counter, 125.5);
+    assert_eq!(resumed.to_save(), Some(save));
+}
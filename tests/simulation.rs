@@ -0,0 +1,690 @@
+//! Scenarios played out on the headless simulation, with scripted input.
+
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use ramble::{
+    enemy::{
+        Enemy, EnemyMovement, EnemyType, PhaseEndCondition, STATUS_TICK_FRAMES, StatusEffectType,
+    },
+    floating_text::FloatingTextManager,
+    items::*,
+    player::*,
+    projectiles::*,
+    rng::Rng,
+    simulation::*,
+    spatial::SpatialGrid,
+    utils::*,
+    worlds::enemies_in_worlds,
+};
+
+fn enemy_type(name: &str) -> &'static EnemyType {
+    enemies_in_worlds()
+        .into_iter()
+        .find(|f| f.name == name)
+        .unwrap_or_else(|| panic!("no enemy named {name}"))
+}
+
+/// A skeleton that stands still and takes a long time to kill, with no resistances, changed by
+/// `f`
+fn dummy(f: impl FnOnce(&mut EnemyType)) -> &'static EnemyType {
+    let mut ty = enemy_type("skeleton").clone();
+    ty.max_health = 1000.0;
+    ty.resistances.clear();
+    ty.phases.truncate(1);
+    ty.phases[0].movement = EnemyMovement::Still;
+    f(&mut ty);
+    Box::leak(Box::new(ty))
+}
+
+fn item(items: &[Item], name: &str) -> Item {
+    get_item_by_name(items, name).unwrap().clone()
+}
+
+/// A room with the player in the middle, holding a weapon if one is given, and one enemy above
+/// them
+fn room<'a>(
+    items: &'a [Item],
+    enchantments: &'a [Enchantment],
+    weapon: Option<Item>,
+    enemy: &'static EnemyType,
+) -> Simulation<'a> {
+    let mut player = Player::new(Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0));
+    *player.slot_mut(InventorySlot::Hand).unwrap() = weapon;
+    let mut sim = Simulation::new(items, enchantments, player, 0);
+    sim.state = GameState::RoundActive;
+    let pos = sim.player.pos - Vec2::new(0.0, 24.0);
+    let enemy = Enemy::new(enemy, pos, 0, &mut Rng::new(0));
+    sim.enemies.push(enemy);
+    sim
+}
+
+/// Input that attacks the first enemy, or doesn't attack at all
+fn attack(sim: &Simulation, attack: bool) -> InputFrame {
+    InputFrame {
+        aim: sim
+            .enemies
+            .first()
+            .map_or(sim.player.pos + RIGHT, |f| f.pos),
+        attack,
+        ..Default::default()
+    }
+}
+
+/// Steps attacking the first enemy until something is hit, and returns the hits of that step
+fn attack_until_hit(sim: &mut Simulation, max: u32) -> Vec<Hit> {
+    for _ in 0..max {
+        sim.step(&attack(sim, true));
+        if !sim.hits.is_empty() {
+            return sim.hits.clone();
+        }
+    }
+    panic!("nothing was hit in {max} steps");
+}
+
+/// Steps until the room is cleared, attacking the enemy that's left. Returns how many steps it
+/// took, or None if it took longer than `max`
+fn steps_to_clear(sim: &mut Simulation, max: u32) -> Option<u32> {
+    for step in 1..=max {
+        sim.step(&attack(sim, true));
+        if sim.enemies.is_empty() {
+            return Some(step);
+        }
+    }
+    None
+}
+
+#[test]
+fn longsword_kills_a_skeleton() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let sword = item(&items, "longsword");
+    let mut sim = room(
+        &items,
+        &enchantments,
+        Some(sword.clone()),
+        enemy_type("skeleton"),
+    );
+    let steps = steps_to_clear(&mut sim, 600).expect("the skeleton should die within 10 seconds");
+    assert!(matches!(sim.state, GameState::PostRound(..)));
+    assert_eq!(sim.player.stats().lives, sim.player.stats().max_lives);
+
+    // the same fight always takes the same number of steps
+    let mut again = room(&items, &enchantments, Some(sword), enemy_type("skeleton"));
+    assert_eq!(steps_to_clear(&mut again, 600), Some(steps));
+}
+
+#[test]
+fn unarmed_player_gets_hit() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let mut sim = room(&items, &enchantments, None, enemy_type("skeleton"));
+    for _ in 0..120 {
+        sim.step(&InputFrame::default());
+    }
+    assert_eq!(sim.enemies.len(), 1);
+    assert!(sim.player.stats().lives < sim.player.stats().max_lives);
+}
+
+#[test]
+fn icicles_freeze_enemies_to_half_speed() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let wand = item(&items, "icicle wand");
+    let mut sim = room(&items, &enchantments, Some(wand), enemy_type("skeleton"));
+    for _ in 0..30 {
+        sim.step(&attack(&sim, true));
+        if !sim.enemies[0].status_effects.is_empty() {
+            break;
+        }
+    }
+    let enemy = &sim.enemies[0];
+    assert_eq!(enemy.status_effects[0].ty, StatusEffectType::Freeze);
+    assert_eq!(enemy.move_multiplier(), 0.5);
+
+    sim.step(&attack(&sim, false));
+    let enemy = &sim.enemies[0];
+    assert_eq!(enemy.pos.distance(enemy.prev_pos), enemy.ty.speed * 0.5);
+}
+
+#[test]
+fn burn_stacks_up_to_its_limit_and_ticks_for_every_stack() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let mut book = item(&items, "book of fireballs");
+    book.internal_stats.status_chances = [(
+        DamageType::Fire,
+        [(StatusEffectType::Burn, 1.0)].into_iter().collect(),
+    )]
+    .into_iter()
+    .collect();
+    let mut sim = room(&items, &enchantments, Some(book), dummy(|_| {}));
+    let mut ticks = Vec::new();
+    for _ in 0..240 {
+        sim.step(&attack(&sim, true));
+        let burn = sim.enemies[0].status_effects.first();
+        assert!(burn.is_none_or(|f| f.stacks <= StatusEffectType::Burn.max_stacks()));
+        // fireballs deal 4, so the rest of the fire damage is from burning
+        ticks.extend(
+            sim.hits
+                .iter()
+                .filter(|f| f.amount != 4.0)
+                .map(|f| f.amount),
+        );
+    }
+    assert_eq!(sim.enemies[0].status_effects[0].stacks, 3);
+    assert_eq!(ticks.first(), Some(&1.0));
+    assert_eq!(ticks.last(), Some(&3.0));
+}
+
+#[test]
+fn resistances_scale_damage_and_full_resistance_is_immune() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let sword = item(&items, "longsword");
+    for (resistance, amount, kind) in [
+        (0.5, 1.0, HitKind::Normal),
+        (-0.5, 3.0, HitKind::Normal),
+        (1.0, 0.0, HitKind::Immune),
+    ] {
+        let enemy = dummy(|f| {
+            f.resistances.insert(DamageType::Slashing, resistance);
+        });
+        let mut sim = room(&items, &enchantments, Some(sword.clone()), enemy);
+        let hits = attack_until_hit(&mut sim, 30);
+        assert_eq!(
+            (hits[0].amount, hits[0].kind),
+            (amount, kind),
+            "{resistance}"
+        );
+        assert_eq!(sim.enemies[0].health, enemy.max_health - amount);
+    }
+
+    // status effects tick through resistances too
+    let enemy = dummy(|f| {
+        f.resistances.insert(DamageType::Fire, 1.0);
+    });
+    let mut sim = room(&items, &enchantments, None, enemy);
+    sim.enemies[0].apply_status(StatusEffectType::Burn);
+    for _ in 0..STATUS_TICK_FRAMES {
+        sim.step(&InputFrame::default());
+    }
+    assert_eq!(sim.hits[0].ty, DamageType::Fire);
+    assert_eq!(sim.hits[0].kind, HitKind::Immune);
+    assert_eq!(sim.enemies[0].health, enemy.max_health);
+}
+
+#[test]
+fn knockback_staggers_enemies_until_they_stop_sliding() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let gloves = item(&items, "boxing gloves");
+    let mut sim = room(&items, &enchantments, Some(gloves.clone()), dummy(|_| {}));
+    attack_until_hit(&mut sim, 30);
+    assert!(sim.enemies[0].sliding());
+    while sim.enemies[0].staggered() {
+        assert_eq!(sim.enemies[0].move_multiplier(), 0.0);
+        sim.step(&attack(&sim, false));
+    }
+    assert!(!sim.enemies[0].sliding());
+
+    // hits that don't break an enemy's poise don't move it
+    let mut sim = room(
+        &items,
+        &enchantments,
+        Some(gloves),
+        dummy(|f| f.poise = 10.0),
+    );
+    attack_until_hit(&mut sim, 30);
+    assert!(!sim.enemies[0].sliding());
+    assert!(!sim.enemies[0].staggered());
+}
+
+#[test]
+fn slamming_in_to_a_wall_deals_impact_damage_and_ends_a_collision_phase() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let enemy = dummy(|f| {
+        f.phases[0].end = PhaseEndCondition::Collision;
+        f.phases.push(f.phases[0].clone());
+        f.phases[1].end = PhaseEndCondition::None;
+    });
+    let gloves = item(&items, "boxing gloves");
+    let mut sim = room(&items, &enchantments, Some(gloves), enemy);
+    // just below the top wall, with the player right under it
+    sim.enemies[0].pos.y = 34.0;
+    sim.player.pos.y = 54.0;
+    let hits = attack_until_hit(&mut sim, 30);
+    assert_eq!(hits[0].amount, 5.0);
+    assert_eq!(sim.enemies[0].phase_index, 0);
+
+    sim.step(&attack(&sim, false));
+    let slam: Vec<f32> = sim.hits.iter().map(|f| f.amount).collect();
+    assert_eq!(slam, [2.5]);
+    assert!(!sim.enemies[0].sliding());
+    assert!(sim.enemies[0].staggered());
+    assert_eq!(sim.enemies[0].phase_index, 1);
+}
+
+#[test]
+fn critical_hits_deal_more_damage_and_trigger_after_plain_hit_effects() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let damage: HashMap<_, _> = [(DamageType::Holy, 1.0)].into_iter().collect();
+    let mut sword = item(&items, "longsword");
+    sword.internal_stats.on_hit_effects = [
+        (OnHitTrigger::Crit, vec![(star_explosion(), damage.clone())]),
+        (OnHitTrigger::Hit, vec![(fire(), damage)]),
+    ]
+    .into_iter()
+    .collect();
+    for (crit_chance, amount, kind, spawned) in [
+        (0.0, 2.0, HitKind::Normal, vec![fire().radius]),
+        (
+            1.0,
+            3.0,
+            HitKind::Crit,
+            vec![fire().radius, star_explosion().radius],
+        ),
+    ] {
+        sword.internal_stats.crit_chance = crit_chance;
+        let mut sim = room(&items, &enchantments, Some(sword.clone()), dummy(|_| {}));
+        let hits = attack_until_hit(&mut sim, 30);
+        assert_eq!((hits[0].amount, hits[0].kind), (amount, kind));
+        let effects: Vec<f32> = sim
+            .projectiles
+            .iter()
+            .filter(|f| f.damage.is_some())
+            .map(|f| f.radius)
+            .collect();
+        assert_eq!(effects, spawned);
+    }
+}
+
+#[test]
+fn arrows_pass_through_as_many_enemies_as_they_pierce() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    for (helmet, hit) in [(None, vec![0]), (Some("piercing circlet"), vec![0, 1])] {
+        let mut sim = room(
+            &items,
+            &enchantments,
+            Some(item(&items, "bow")),
+            dummy(|_| {}),
+        );
+        if let Some(helmet) = helmet {
+            sim.player.give_item(item(&items, helmet));
+        }
+        let pos = sim.enemies[0].pos - Vec2::new(0.0, 24.0);
+        let behind = Enemy::new(sim.enemies[0].ty, pos, 1, &mut Rng::new(0));
+        sim.enemies.push(behind);
+        // an uncharged shot, fired when the attack is let go
+        let mut ids = Vec::new();
+        for step in 0..20 {
+            sim.step(&attack(&sim, step == 0));
+            ids.extend(sim.hits.iter().map(|f| f.enemy_id));
+        }
+        assert_eq!(ids, hit, "{helmet:?}");
+    }
+}
+
+#[test]
+fn projectiles_bounce_off_walls_as_many_times_as_they_can() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    for (helmet, bounced) in [(None, false), (Some("crown of ricochets"), true)] {
+        let dart = item(&items, "razor dart");
+        let mut sim = room(&items, &enchantments, Some(dart), dummy(|_| {}));
+        if let Some(helmet) = helmet {
+            sim.player.give_item(item(&items, helmet));
+        }
+        // fire at the left wall from right next to it
+        sim.player.pos.x = 20.0;
+        let input = InputFrame {
+            aim: sim.player.pos - RIGHT,
+            attack: true,
+            ..Default::default()
+        };
+        sim.step(&input);
+        for _ in 0..5 {
+            sim.step(&InputFrame::default());
+        }
+        let dart = sim.projectiles.iter().find(|f| f.player_owned);
+        assert_eq!(dart.is_some(), bounced, "{helmet:?}");
+        assert!(dart.is_none_or(|f| f.direction.x > 0.0 && f.bounces == 0));
+    }
+}
+
+#[test]
+fn attacks_fan_out_their_projectiles_and_burst_fire_again() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let mut sword = item(&items, "longsword");
+    sword.internal_stats.burst = 1;
+    let mut sim = room(&items, &enchantments, Some(sword), dummy(|_| {}));
+    sim.player.give_item(item(&items, "split shot"));
+    // keep aiming at where the enemy started, since the rest of the burst follows the aim
+    let target = sim.enemies[0].pos;
+    let aim = (target - sim.player.pos).normalize();
+
+    let mut fired = Vec::new();
+    for step in 0..20 {
+        sim.step(&InputFrame {
+            aim: target,
+            attack: step == 0,
+            ..Default::default()
+        });
+        for projectile in sim.projectiles.iter() {
+            if projectile.player_owned && projectile.prev_pos.is_none() {
+                let angle = aim.angle_between(projectile.direction).to_degrees().round();
+                fired.push((step, angle));
+            }
+        }
+    }
+    let burst = BURST_DELAY as u32;
+    assert_eq!(
+        fired,
+        [
+            (0, -15.0),
+            (0, 0.0),
+            (0, 15.0),
+            (burst, -15.0),
+            (burst, 0.0),
+            (burst, 15.0)
+        ]
+    );
+}
+
+#[test]
+fn homing_bolts_turn_towards_the_closest_enemy_they_havent_hit() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let start = Vec2::new(SCREEN_WIDTH / 2.0, 150.0);
+    let near = start + Vec2::new(30.0, -30.0);
+    let further = start + Vec2::new(30.0, 50.0);
+    let out_of_range = start - Vec2::new(0.0, HOMING_RADIUS + 4.0);
+    for (enemies, hit, target) in [
+        (vec![near, further], vec![], Some(near)),
+        (vec![near, further], vec![0], Some(further)),
+        (vec![out_of_range], vec![], None),
+    ] {
+        let mut sim = room(&items, &enchantments, None, dummy(|_| {}));
+        let ty = sim.enemies[0].ty;
+        sim.enemies = enemies
+            .iter()
+            .enumerate()
+            .map(|(id, pos)| Enemy::new(ty, *pos, id, &mut Rng::new(0)))
+            .collect();
+        let mut bolt = homing_bolt();
+        bolt.pos = start;
+        bolt.direction = RIGHT;
+        bolt.player_owned = true;
+        bolt.stats = Some(sim.player.stats());
+        bolt.hit_enemies = hit;
+        sim.projectiles.push(bolt);
+        sim.step(&InputFrame::default());
+        let direction = sim.projectiles[0].direction;
+        match target {
+            Some(target) => assert_eq!(direction.y.signum(), (target.y - start.y).signum()),
+            None => assert_eq!(direction, RIGHT),
+        }
+    }
+}
+
+#[test]
+fn behaviors_tick_before_the_projectile_moves() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let mut sim = room(&items, &enchantments, None, dummy(|_| {}));
+    let start = Vec2::new(40.0, 60.0);
+    sim.projectiles.push(Projectile {
+        pos: start,
+        direction: RIGHT,
+        speed: 4.0,
+        lifetime: 60,
+        behaviors: vec![
+            ProjectileBehavior::Drag(0.5),
+            ProjectileBehavior::Trail(Box::new(fire()), 1),
+        ],
+        ..BASE_PROJECTILE
+    });
+    sim.step(&InputFrame::default());
+    // slowed down to half speed, and left the trail where it started the step
+    let positions: Vec<Vec2> = sim.projectiles.iter().map(|f| f.pos).collect();
+    assert_eq!(positions, [start + RIGHT * 2.0, start]);
+}
+
+#[test]
+fn sticky_projectiles_stop_at_walls_and_split_where_they_stopped() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let mut sim = room(&items, &enchantments, None, dummy(|_| {}));
+    sim.projectiles.push(Projectile {
+        pos: Vec2::new(10.0, 60.0),
+        direction: -RIGHT,
+        speed: 3.0,
+        lifetime: 10,
+        behaviors: vec![
+            ProjectileBehavior::Stick,
+            ProjectileBehavior::SplitOnExpire(Box::new(fire()), 4),
+        ],
+        ..BASE_PROJECTILE
+    });
+    for _ in 0..9 {
+        sim.step(&InputFrame::default());
+    }
+    let stuck = Vec2::new(4.0, 60.0);
+    assert_eq!(sim.projectiles.len(), 1);
+    assert_eq!(
+        (sim.projectiles[0].pos, sim.projectiles[0].speed),
+        (stuck, 0.0)
+    );
+
+    sim.step(&InputFrame::default());
+    assert_eq!(sim.projectiles.len(), 4);
+    assert!(sim.projectiles.iter().all(|f| f.pos == stuck));
+}
+
+#[test]
+fn slashes_reflect_or_destroy_the_enemy_projectiles_they_touch() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let sword = item(&items, "longsword");
+    let mut sim = room(&items, &enchantments, Some(sword), dummy(|_| {}));
+    for projectile in [fireball(), slow_arrow(), arrow()] {
+        sim.projectiles.push(Projectile {
+            pos: sim.player.pos + RIGHT * 20.0,
+            direction: -RIGHT,
+            ..projectile
+        });
+    }
+    sim.step(&InputFrame {
+        aim: sim.player.pos + RIGHT,
+        attack: true,
+        ..Default::default()
+    });
+    // the fireball is sent back, the slow arrow is destroyed, and the arrow can't be parried,
+    // which leaves them and the slash
+    let player_owned: Vec<bool> = sim.projectiles.iter().map(|f| f.player_owned).collect();
+    assert_eq!(player_owned, [true, false, true]);
+    assert!(sim.projectiles[0].reflectable);
+    assert_eq!(sim.projectiles[0].direction, RIGHT);
+    assert_eq!(sim.projectiles[1].speed, arrow().speed);
+    assert!(sim.projectiles[2].parries);
+}
+
+#[test]
+fn rolling_through_a_projectile_right_away_is_a_perfect_dodge() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let mut sim = room(&items, &enchantments, None, dummy(|_| {}));
+    sim.projectiles.push(Projectile {
+        pos: sim.player.pos,
+        lifetime: 60,
+        radius: 20.0,
+        ..BASE_PROJECTILE
+    });
+    sim.player.attack_counter = 30.0;
+    sim.step(&InputFrame {
+        move_vector: RIGHT,
+        roll: true,
+        ..Default::default()
+    });
+    assert_eq!(sim.player.stats().lives, sim.player.stats().max_lives);
+    assert_eq!(sim.player.perfect_dodge_frames, PERFECT_DODGE_FRAMES);
+    assert_eq!(sim.player.roll_counter, 0.0);
+    assert_eq!(sim.player.attack_counter, 0.0);
+
+    // only once per dodge
+    sim.player.roll_counter = 60.0;
+    sim.step(&InputFrame::default());
+    assert_eq!(sim.player.perfect_dodge_frames, PERFECT_DODGE_FRAMES - 1);
+    assert_eq!(sim.player.roll_counter, 59.0);
+}
+
+#[test]
+fn charged_shots_scale_with_how_long_the_attack_was_held() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    // the bow charges fully in 40 frames, to 3 times the damage and 1.5 times the speed
+    for (held, damage, speed) in [
+        (1, 1.0, 1.0),
+        (21, 2.0, 1.25),
+        (41, 3.0, 1.5),
+        (100, 3.0, 1.5),
+    ] {
+        let mut sim = room(
+            &items,
+            &enchantments,
+            Some(item(&items, "bow")),
+            dummy(|_| {}),
+        );
+        // aim away from the enemy, so the arrow is still around after the step it's fired
+        let mut input = InputFrame {
+            aim: sim.player.pos + Vec2::new(0.0, 1.0),
+            attack: true,
+            ..Default::default()
+        };
+        for _ in 0..held {
+            sim.step(&input);
+        }
+        assert!(sim.projectiles.is_empty());
+        input.attack = false;
+        sim.step(&input);
+        let shot = &sim.projectiles[0];
+        assert_eq!(shot.damage_scale, damage, "{held}");
+        assert_eq!(shot.speed, arrow().speed * speed, "{held}");
+    }
+
+    // moving at half speed while charging
+    let mut sim = room(
+        &items,
+        &enchantments,
+        Some(item(&items, "bow")),
+        dummy(|_| {}),
+    );
+    let input = InputFrame {
+        move_vector: RIGHT,
+        ..attack(&sim, true)
+    };
+    for _ in 0..2 {
+        sim.step(&input);
+    }
+    let speed = sim.player.stats().move_speed;
+    assert_eq!(sim.player.pos.x - sim.player.prev_pos.x, speed * 0.5);
+}
+
+#[test]
+fn active_items_wait_for_their_cooldown() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    // the bomb bag's cooldown is 300 frames, and the hourglass makes cooldowns 30% shorter
+    for (talisman, cooldown_mod, thrown) in [
+        (None, 0.0, vec![0, 300]),
+        (Some("hourglass"), 0.0, vec![0, 210]),
+        // never shorter than a frame
+        (None, -5.0, (0..320).collect()),
+    ] {
+        let mut sim = room(&items, &enchantments, None, dummy(|_| {}));
+        sim.player.give_item(item(&items, "bomb bag"));
+        if let Some(talisman) = talisman {
+            sim.player.give_item(item(&items, talisman));
+        }
+        sim.player.internal_stats_mut().cooldown_mod = cooldown_mod;
+        let mut steps = Vec::new();
+        for step in 0..320 {
+            sim.step(&InputFrame {
+                ability: true,
+                ..attack(&sim, false)
+            });
+            // bombs only go off once they expire, so they don't hit enemies themselves
+            if sim
+                .projectiles
+                .iter()
+                .any(|f| f.prev_pos.is_none() && !f.hits_enemies())
+            {
+                steps.push(step);
+            }
+        }
+        assert_eq!(steps, thrown, "{talisman:?} {cooldown_mod}");
+    }
+}
+
+#[test]
+fn hits_close_together_merge_in_to_one_damage_number() {
+    let items = get_items();
+    let enchantments = get_enchantments();
+    let sword = item(&items, "longsword");
+    let mut sim = room(
+        &items,
+        &enchantments,
+        Some(sword),
+        dummy(|f| f.poise = 10.0),
+    );
+    sim.player.give_item(item(&items, "split shot"));
+    let mut texts = FloatingTextManager::default();
+    let mut play = |sim: &mut Simulation, attacking: bool, steps: u32| {
+        for step in 0..steps {
+            sim.step(&attack(sim, attacking && step == 0));
+            texts.step(&sim.hits);
+        }
+        texts.amounts(0)
+    };
+    // the three slashes of an attack add up
+    assert_eq!(play(&mut sim, true, 10), [6.0]);
+    // the next attack is too late to be added to it
+    play(&mut sim, false, 20);
+    assert_eq!(play(&mut sim, true, 10), [6.0, 6.0]);
+    assert_eq!(play(&mut sim, false, 60), []);
+}
+
+#[test]
+fn grid_queries_find_everything_a_full_search_does() {
+    let mut rng = Rng::new(5);
+    // some just outside the room, like enemies being knocked in to a wall
+    let point = |rng: &mut Rng| {
+        Vec2::new(
+            rng.gen_range(-20.0, SCREEN_WIDTH + 20.0),
+            rng.gen_range(-20.0, SCREEN_HEIGHT + 20.0),
+        )
+    };
+    let positions: Vec<Vec2> = (0..300).map(|_| point(&mut rng)).collect();
+    let mut grid = SpatialGrid::default();
+    grid.rebuild(positions.iter().copied());
+    let mut found = Vec::new();
+    for _ in 0..200 {
+        let pos = point(&mut rng);
+        let radius = rng.gen_range(0.0, 100.0);
+        grid.query(pos, radius, &mut found);
+        assert!(found.is_sorted());
+        let within: Vec<usize> = found
+            .iter()
+            .copied()
+            .filter(|f| positions[*f].distance(pos) <= radius)
+            .collect();
+        let expected: Vec<usize> = (0..positions.len())
+            .filter(|f| positions[*f].distance(pos) <= radius)
+            .collect();
+        assert_eq!(within, expected, "{pos} {radius}");
+    }
+}
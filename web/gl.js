@@ -2082,3 +2082,35 @@ function load(wasm_path) {
         return cid;
     }
 }());
+(function () {
+    "use strict";
+
+    // localStorage access for saving runs between sessions
+
+    function read_string(ptr, len) {
+        return new TextDecoder().decode(new Uint8Array(wasm_memory.buffer, ptr, len));
+    }
+
+    function register_plugin(importObject) {
+        importObject.env.ramble_storage_set = function (key, key_len, value, value_len) {
+            localStorage.setItem(read_string(key, key_len), read_string(value, value_len));
+        }
+        importObject.env.ramble_storage_len = function (key, key_len) {
+            var value = localStorage.getItem(read_string(key, key_len));
+            if (value == null) {
+                return -1;
+            }
+            return new TextEncoder().encode(value).length;
+        }
+        importObject.env.ramble_storage_get = function (key, key_len, buffer, buffer_len) {
+            var value = localStorage.getItem(read_string(key, key_len)) || "";
+            var bytes = new TextEncoder().encode(value).subarray(0, buffer_len);
+            new Uint8Array(wasm_memory.buffer, buffer, buffer_len).set(bytes);
+        }
+        importObject.env.ramble_storage_remove = function (key, key_len) {
+            localStorage.removeItem(read_string(key, key_len));
+        }
+    }
+
+    miniquad_add_plugin({ register_plugin, version: 1, name: "ramble_storage" });
+}());